@@ -2,7 +2,10 @@ use std::sync::Arc;
 
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use yang2::context::{Context, ContextFlags};
-use yang2::data::{Data, DataTree, DataValidationFlags, DataDiffFlags};
+use yang2::data::{
+    Data, DataDiffFlags, DataFormat, DataParserFlags, DataPrinterFlags,
+    DataTree, DataValidationFlags,
+};
 
 static SEARCH_DIR: &str = "./assets/yang/";
 
@@ -85,7 +88,9 @@ fn criterion_benchmark(c: &mut Criterion) {
                 b.iter(|| {
                     for dnode in dtree.traverse() {
                         let path = dnode.path();
-                        dtree.find_path(&path).expect("Failed to find data");
+                        dtree
+                            .find_path(&path, false)
+                            .expect("Failed to find data");
                     }
                 });
             },
@@ -116,6 +121,46 @@ fn criterion_benchmark(c: &mut Criterion) {
         );
     }
     group.finish();
+
+    // Prepare DataTree.parse_string() vs DataTree.parse_fast() benchmark.
+    let mut group = c.benchmark_group("DataTree.parse_fast() / tree size");
+    for size in &tree_sizes {
+        // Create artificial JSON data.
+        let data = data_generate(&ctx, *size)
+            .print_string(DataFormat::JSON, DataPrinterFlags::WITH_SIBLINGS)
+            .expect("Failed to print data tree")
+            .unwrap();
+
+        // Run benchmarks.
+        group.bench_with_input(
+            BenchmarkId::new("parse_string", size),
+            size,
+            |b, _| {
+                b.iter(|| {
+                    DataTree::parse_string(
+                        &ctx,
+                        &data,
+                        DataFormat::JSON,
+                        DataParserFlags::empty(),
+                        DataValidationFlags::NO_STATE
+                            | DataValidationFlags::PRESENT,
+                    )
+                    .expect("Failed to parse data tree")
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("parse_fast", size),
+            size,
+            |b, _| {
+                b.iter(|| {
+                    DataTree::parse_fast(&ctx, &data, DataFormat::JSON)
+                        .expect("Failed to parse data tree")
+                })
+            },
+        );
+    }
+    group.finish();
 }
 
 criterion_group!(benches, criterion_benchmark);
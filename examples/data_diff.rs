@@ -95,7 +95,7 @@ fn main() -> std::io::Result<()> {
     .expect("Failed to print data diff");
 
     println!("Comparing data trees (manual iteration):");
-    for (op, dnode) in diff.iter() {
+    for (op, dnode, _) in diff.iter() {
         println!(" {:?}: {} ({:?})", op, dnode.path(), dnode.value());
     }
 
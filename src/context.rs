@@ -8,10 +8,10 @@
 
 use bitflags::bitflags;
 use std::collections::HashMap;
-use std::ffi::CString;
-use std::os::raw::{c_char, c_void};
+use std::ffi::{CStr, CString, OsStr};
+use std::os::raw::{c_char, c_uint, c_void};
 use std::os::unix::ffi::OsStrExt;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::slice;
 use std::sync::Once;
 
@@ -58,6 +58,20 @@ bitflags! {
         /// directory, which is by default searched automatically (despite not
         /// recursively).
         const DISABLE_SEARCHDIR_CWD = ffi::LY_CTX_DISABLE_SEARCHDIR_CWD as u16;
+
+        /// For all compiled nodes, their private objects are set to the
+        /// corresponding parsed nodes. This is required for accessing
+        /// information only available in the parsed schema, such as the names
+        /// of derived types (see
+        /// [`SchemaLeafType::type_name`](crate::schema::SchemaLeafType::type_name)).
+        /// Descriptions, references and extension instances are kept in the
+        /// compiled schema and don't require this flag.
+        ///
+        /// When set, the private pointers of schema nodes are reserved for
+        /// libyang and can't be used to store user data (see
+        /// [`SchemaNode::set_private`](crate::schema::SchemaNode::set_private)).
+        /// This option can also be set or unset on an existing context.
+        const SET_PRIV_PARSED = ffi::LY_CTX_SET_PRIV_PARSED as u16;
     }
 }
 
@@ -74,6 +88,20 @@ pub struct EmbeddedModuleKey {
 /// A hashmap containing embedded YANG modules.
 pub type EmbeddedModules = HashMap<EmbeddedModuleKey, &'static str>;
 
+/// Standard NETCONF modules loaded by [`Context::new_netconf`], along with the
+/// features enabled in each of them (`"*"` enables all features).
+///
+/// The ietf-yang-library module isn't listed since it's implemented
+/// internally by libyang. A different module set can be loaded using
+/// [`Context::new_with_modules`].
+pub const NETCONF_MODULES: &[(&str, &[&str])] = &[
+    ("ietf-netconf", &["*"]),
+    ("ietf-netconf-with-defaults", &[]),
+    ("ietf-netconf-notifications", &[]),
+    ("ietf-netconf-monitoring", &[]),
+    ("ietf-netconf-acm", &[]),
+];
+
 // ===== impl Context =====
 
 impl Context {
@@ -84,33 +112,94 @@ impl Context {
     /// libyang is holding all schemas (and other internal information)
     /// according to which the data trees will be processed and validated.
     pub fn new(options: ContextFlags) -> Result<Context> {
-        static INIT: Once = Once::new();
         let mut context = std::ptr::null_mut();
         let ctx_ptr = &mut context;
 
-        // Initialization routine that is called only once when the first YANG
-        // context is created.
-        INIT.call_once(|| {
-            // Disable automatic logging to stderr in order to give users more
-            // control over the handling of errors.
-            unsafe { ffi::ly_log_options(ffi::LY_LOSTORE_LAST) };
-        });
+        init();
 
         let ret =
             unsafe { ffi::ly_ctx_new(std::ptr::null(), options.bits, ctx_ptr) };
         if ret != ffi::LY_ERR::LY_SUCCESS {
             // Need to construct error structure by hand.
-            return Err(Error {
-                errcode: ret,
-                msg: None,
-                path: None,
-                apptag: None,
-            });
+            return Err(Error::with_msg(ret, "Failed to create context", None));
         }
 
         Ok(Context { raw: context })
     }
 
+    /// Create libyang context from a snapshot of the module set of another
+    /// context (see [`Context::snapshot`]).
+    ///
+    /// The modules listed in the snapshot are loaded from the given search
+    /// directories, with the same revisions, features and implemented state
+    /// as in the original context. The search directories are kept in the
+    /// context.
+    pub fn from_snapshot<P: AsRef<Path>>(
+        options: ContextFlags,
+        search_dirs: &[P],
+        snapshot: &str,
+    ) -> Result<Context> {
+        let mut context = std::ptr::null_mut();
+        let ctx_ptr = &mut context;
+
+        init();
+
+        // Multiple search directories are separated by colons.
+        let search_dirs = search_dirs
+            .iter()
+            .map(|search_dir| search_dir.as_ref().as_os_str().as_bytes())
+            .collect::<Vec<_>>()
+            .join(&b':');
+        let search_dirs = CString::new(search_dirs).unwrap();
+        let snapshot = CString::new(snapshot).unwrap();
+        let ret = unsafe {
+            ffi::ly_ctx_new_ylmem(
+                search_dirs.as_ptr(),
+                snapshot.as_ptr(),
+                ffi::LYD_FORMAT::LYD_JSON,
+                options.bits as i32,
+                ctx_ptr,
+            )
+        };
+        if ret != ffi::LY_ERR::LY_SUCCESS {
+            // Need to construct error structure by hand.
+            return Err(Error::with_msg(ret, "Failed to create context", None));
+        }
+
+        Ok(Context { raw: context })
+    }
+
+    /// Create libyang context preloaded with the standard NETCONF modules
+    /// listed in [`NETCONF_MODULES`].
+    ///
+    /// The modules are looked up in the given search directories, which are
+    /// kept in the context.
+    pub fn new_netconf<P: AsRef<Path>>(search_dirs: &[P]) -> Result<Context> {
+        Context::new_with_modules(
+            ContextFlags::empty(),
+            search_dirs,
+            NETCONF_MODULES,
+        )
+    }
+
+    /// Create libyang context with the given search directories and load the
+    /// given modules into it, along with their enabled features.
+    pub fn new_with_modules<P: AsRef<Path>>(
+        options: ContextFlags,
+        search_dirs: &[P],
+        modules: &[(&str, &[&str])],
+    ) -> Result<Context> {
+        let mut context = Context::new(options)?;
+        for search_dir in search_dirs {
+            context.set_searchdir(search_dir)?;
+        }
+        for (name, features) in modules {
+            context.load_module(name, None, features)?;
+        }
+
+        Ok(context)
+    }
+
     /// Add the search path into libyang context.
     pub fn set_searchdir<P: AsRef<Path>>(
         &mut self,
@@ -171,6 +260,27 @@ impl Context {
         Ok(())
     }
 
+    /// Get the search paths currently set in the libyang context, in the
+    /// order they were added.
+    ///
+    /// Note that libyang stores the search paths as canonical absolute paths.
+    pub fn searchdirs(&self) -> Vec<PathBuf> {
+        let mut searchdirs = Vec::new();
+        let mut rdirs = unsafe { ffi::ly_ctx_get_searchdirs(self.raw) };
+        if rdirs.is_null() {
+            return searchdirs;
+        }
+        unsafe {
+            while !(*rdirs).is_null() {
+                let dir = CStr::from_ptr(*rdirs).to_bytes();
+                searchdirs.push(PathBuf::from(OsStr::from_bytes(dir)));
+                rdirs = rdirs.add(1);
+            }
+        }
+
+        searchdirs
+    }
+
     /// Set hash map containing embedded YANG modules, which are loaded on
     /// demand.
     pub fn set_embedded_modules(&mut self, modules: &EmbeddedModules) {
@@ -221,6 +331,85 @@ impl Context {
         unsafe { ffi::ly_ctx_get_change_count(self.raw) }
     }
 
+    /// Get the hash of all the modules in the context. The hash changes
+    /// whenever a module is added, implemented or has its features changed.
+    pub fn get_modules_hash(&self) -> u32 {
+        unsafe { ffi::ly_ctx_get_modules_hash(self.raw) }
+    }
+
+    /// Check whether the given LYB data was printed using a context with the
+    /// same set of modules as this one, and hence can be parsed using it.
+    ///
+    /// This allows to detect stale LYB data (e.g. cached on disk) before
+    /// attempting to parse it.
+    ///
+    /// The LYB header is decoded here rather than by libyang, following the
+    /// layout used by libyang 2.1 (the libyang2 sources the FFI bindings are
+    /// generated from, commit 7e5ea21). The LYB format version of the data
+    /// is checked against the one libyang prints, so data printed by other
+    /// versions of the format isn't considered compatible.
+    pub fn lyb_compatible(&self, data: &[u8]) -> bool {
+        // The LYB header consists of the "lyb" magic number, a version byte
+        // and the hash of the context modules, in native byte order.
+        if data.len() < 8 || &data[0..3] != b"lyb" {
+            return false;
+        }
+        match lyb_version() {
+            Some(version) if version == data[3] => (),
+            _ => return false,
+        }
+        let mut hash = [0; 4];
+        hash.copy_from_slice(&data[4..8]);
+        u32::from_ne_bytes(hash) == self.get_modules_hash()
+    }
+
+    /// Take a snapshot of the module set of the context, which can be used to
+    /// recreate an equivalent context later using [`Context::from_snapshot`]
+    /// (e.g. across application restarts).
+    ///
+    /// The snapshot is the ietf-yang-library data of the context (JSON
+    /// format), listing all modules along with their revisions and enabled
+    /// features. It doesn't include the module sources, so the modules are
+    /// still parsed and compiled when the context is recreated. This requires
+    /// the context to be created without the
+    /// [`ContextFlags::NO_YANGLIBRARY`] option.
+    pub fn snapshot(&self) -> Result<String> {
+        let mut rnode = std::ptr::null_mut();
+        let rnode_ptr = &mut rnode;
+        let content_id = CString::new("%u").unwrap();
+
+        let ret = unsafe {
+            ffi::ly_ctx_get_yanglib_data(
+                self.raw,
+                rnode_ptr,
+                content_id.as_ptr(),
+                self.get_module_set_id() as c_uint,
+            )
+        };
+        if ret != ffi::LY_ERR::LY_SUCCESS {
+            return Err(Error::new(self));
+        }
+
+        let mut cstr = std::ptr::null_mut();
+        let cstr_ptr = &mut cstr;
+        let ret = unsafe {
+            ffi::lyd_print_mem(
+                cstr_ptr,
+                rnode,
+                ffi::LYD_FORMAT::LYD_JSON,
+                ffi::LYD_PRINT_WITHSIBLINGS,
+            )
+        };
+        unsafe { ffi::lyd_free_all(rnode) };
+        if ret != ffi::LY_ERR::LY_SUCCESS {
+            return Err(Error::new(self));
+        }
+
+        let snapshot = char_ptr_to_opt_string(cstr).unwrap_or_default();
+        unsafe { ffi::free(cstr as *mut c_void) };
+        Ok(snapshot)
+    }
+
     /// Get YANG module of the given name and revision.
     ///
     /// If the revision is not specified, the schema with no revision is
@@ -348,12 +537,58 @@ impl Context {
         SchemaModules::new(self, skip_internal)
     }
 
+    /// Get the prefix to namespace mapping of all loaded modules, as used when
+    /// printing data in the XML format (`xmlns:<prefix>="<namespace>"`).
+    ///
+    /// Modules loaded in multiple revisions share the same prefix and
+    /// namespace, so they are listed only once.
+    pub fn namespace_prefixes(&self) -> Vec<(String, String)> {
+        let mut prefixes: Vec<(String, String)> = Vec::new();
+        for module in self.modules(false) {
+            let entry =
+                (module.prefix().to_owned(), module.namespace().to_owned());
+            if !prefixes.contains(&entry) {
+                prefixes.push(entry);
+            }
+        }
+        prefixes
+    }
+
     /// Returns an iterator over all data nodes from all modules in the YANG
     /// context (depth-first search algorithm).
     pub fn traverse(&self) -> impl Iterator<Item = SchemaNode<'_>> {
         self.modules(false).flat_map(|module| module.traverse())
     }
 
+    /// Returns an iterator over the RPCs from all modules in the YANG context.
+    pub fn rpcs(&self) -> impl Iterator<Item = SchemaNode<'_>> {
+        self.modules(false).flat_map(|module| module.rpcs())
+    }
+
+    /// Returns an iterator over the actions from all modules in the YANG
+    /// context.
+    pub fn actions(&self) -> impl Iterator<Item = SchemaNode<'_>> {
+        self.modules(false)
+            .flat_map(|module| module.data())
+            .flat_map(|snode| snode.traverse())
+            .filter_map(|snode| snode.actions())
+            .flatten()
+    }
+
+    /// Returns an iterator over the notifications from all modules in the YANG
+    /// context, including the ones nested in data nodes.
+    pub fn notifications(&self) -> impl Iterator<Item = SchemaNode<'_>> {
+        let nested = self
+            .modules(false)
+            .flat_map(|module| module.data())
+            .flat_map(|snode| snode.traverse())
+            .filter_map(|snode| snode.notifications())
+            .flatten();
+        self.modules(false)
+            .flat_map(|module| module.notifications())
+            .chain(nested)
+    }
+
     /// Reset cached latest revision information of the schemas in the context.
     ///
     /// When a (sub)module is imported/included without revision, the latest
@@ -433,6 +668,81 @@ impl Context {
         Ok(unsafe { SchemaModule::from_raw(self, module as *mut _) })
     }
 
+    /// Load a module like [`Context::load_module`], along with all modules
+    /// found in the search directories that deviate it.
+    ///
+    /// Deviations are only applied by implemented modules, so modules that
+    /// deviate the loaded one can silently change the validation behavior
+    /// when missing. The search directories are looked up recursively for
+    /// YANG files (YIN files aren't considered) containing deviation
+    /// statements. These are parsed by libyang in a single scratch context,
+    /// along with the loaded module, to find out which of them deviate it.
+    /// The matching modules are then parsed from their files as implemented
+    /// modules, without any features enabled.
+    ///
+    /// The module and the deviating modules are compiled at once, so the
+    /// context is left untouched if any of them fails to load. If the context
+    /// was created with explicit compilation, they're only compiled along
+    /// with the other pending changes.
+    pub fn load_module_with_deviations(
+        &mut self,
+        name: &str,
+        revision: Option<&str>,
+        features: &[&str],
+    ) -> Result<SchemaModule<'_>> {
+        let files =
+            deviation_files(&self.searchdirs(), name, revision, features);
+
+        let options = unsafe { ffi::ly_ctx_get_options(self.raw) };
+        let explicit_compile = ffi::LY_CTX_EXPLICIT_COMPILE as u16;
+        let compile = options & explicit_compile == 0;
+        if compile {
+            unsafe { ffi::ly_ctx_set_options(self.raw, explicit_compile) };
+        }
+        let result = self
+            .load_module_deviated(name, revision, features, &files, compile);
+        if compile {
+            unsafe { ffi::ly_ctx_unset_options(self.raw, explicit_compile) };
+        }
+        let rmodule = result?;
+
+        Ok(unsafe { SchemaModule::from_raw(self, rmodule) })
+    }
+
+    // Load a module along with the deviating modules of the given files. In
+    // case of errors, libyang reverts all changes not compiled yet.
+    fn load_module_deviated(
+        &mut self,
+        name: &str,
+        revision: Option<&str>,
+        features: &[&str],
+        files: &[CString],
+        compile: bool,
+    ) -> Result<*mut ffi::lys_module> {
+        let rmodule = self.load_module(name, revision, features)?.raw;
+        for file in files {
+            let ret = unsafe {
+                ffi::lys_parse_path(
+                    self.raw,
+                    file.as_ptr(),
+                    ffi::LYS_INFORMAT::LYS_IN_YANG,
+                    std::ptr::null_mut(),
+                )
+            };
+            if ret != ffi::LY_ERR::LY_SUCCESS {
+                return Err(Error::new(self));
+            }
+        }
+        if compile {
+            let ret = unsafe { ffi::ly_ctx_compile(self.raw) };
+            if ret != ffi::LY_ERR::LY_SUCCESS {
+                return Err(Error::new(self));
+            }
+        }
+
+        Ok(rmodule)
+    }
+
     /// Evaluate an xpath expression on schema nodes.
     pub fn find_xpath(&self, path: &str) -> Result<Set<'_, SchemaNode<'_>>> {
         let path = CString::new(path).unwrap();
@@ -477,6 +787,26 @@ impl Context {
 
         Ok(unsafe { SchemaNode::from_raw(self, rnode as *mut _) })
     }
+
+    /// Get a schema node based on the given data path (JSON format).
+    ///
+    /// Unlike [`Context::find_path`], a well-formed path that doesn't match
+    /// any schema node isn't considered an error, and `None` is returned
+    /// instead. An error is still returned for invalid paths (e.g. syntax
+    /// errors or unknown modules).
+    pub fn find_schema(&self, path: &str) -> Result<Option<SchemaNode<'_>>> {
+        let error = match self.find_path(path) {
+            Ok(snode) => return Ok(Some(snode)),
+            Err(error) => error,
+        };
+
+        // Evaluating the path as an XPath expression doesn't fail when no
+        // schema node is matched.
+        match self.find_xpath(path).map(|set| set.count()) {
+            Ok(0) => Ok(None),
+            _ => Err(error),
+        }
+    }
 }
 
 unsafe impl Send for Context {}
@@ -508,6 +838,132 @@ impl EmbeddedModuleKey {
 
 // ===== helper functions =====
 
+// Initialization routine that is called only once when the first YANG context
+// is created.
+fn init() {
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        // Disable automatic logging to stderr in order to give users more
+        // control over the handling of errors.
+        unsafe { ffi::ly_log_options(ffi::LY_LOSTORE_LAST) };
+    });
+}
+
+// Returns the LYB format version written by libyang, taken from the header
+// of empty LYB data.
+fn lyb_version() -> Option<u8> {
+    let mut data = std::ptr::null_mut();
+    let ret = unsafe {
+        ffi::lyd_print_mem(
+            &mut data,
+            std::ptr::null(),
+            ffi::LYD_FORMAT::LYD_LYB,
+            0,
+        )
+    };
+    if ret != ffi::LY_ERR::LY_SUCCESS || data.is_null() {
+        return None;
+    }
+    let version = if unsafe { ffi::lyd_lyb_data_length(data) } >= 4 {
+        Some(unsafe { *(data as *const u8).add(3) })
+    } else {
+        None
+    };
+    unsafe { ffi::free(data as *mut c_void) };
+
+    version
+}
+
+// Look up the given directory recursively for YANG files, adding their paths
+// to the provided list.
+fn find_yang_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.is_dir() {
+            find_yang_files(&path, files);
+        } else if path.extension() == Some(OsStr::new("yang")) {
+            files.push(path);
+        }
+    }
+}
+
+// Find the files of the YANG modules in the given search directories that
+// deviate the target module.
+//
+// The target module and all modules containing deviation statements are
+// loaded in a scratch context, so that the deviations are resolved by libyang
+// itself.
+fn deviation_files(
+    search_dirs: &[PathBuf],
+    target: &str,
+    revision: Option<&str>,
+    features: &[&str],
+) -> Vec<CString> {
+    let mut ctx = match Context::new(
+        ContextFlags::NO_YANGLIBRARY | ContextFlags::DISABLE_SEARCHDIR_CWD,
+    ) {
+        Ok(ctx) => ctx,
+        Err(_) => return Vec::new(),
+    };
+    for search_dir in search_dirs {
+        let _ = ctx.set_searchdir(search_dir);
+    }
+    if ctx.load_module(target, revision, features).is_err() {
+        return Vec::new();
+    }
+
+    let mut files = Vec::new();
+    for search_dir in search_dirs {
+        find_yang_files(search_dir, &mut files);
+    }
+    for file in files.iter().filter(|file| has_deviations(file)) {
+        // Modules that fail to load can't deviate the target module.
+        let file = CString::new(file.as_os_str().as_bytes()).unwrap();
+        unsafe {
+            ffi::lys_parse_path(
+                ctx.raw,
+                file.as_ptr(),
+                ffi::LYS_INFORMAT::LYS_IN_YANG,
+                std::ptr::null_mut(),
+            )
+        };
+    }
+
+    let module = match ctx.get_module_implemented(target) {
+        Some(module) => module,
+        None => return Vec::new(),
+    };
+    module
+        .deviated_by()
+        .iter()
+        .filter_map(|module| {
+            char_ptr_to_opt_str(unsafe { (*module.raw).filepath })
+        })
+        .map(|file| CString::new(file).unwrap())
+        .collect()
+}
+
+// Check whether the given YANG file contains a deviation statement, which is
+// assumed to start its own line.
+fn has_deviations(file: &Path) -> bool {
+    let text = match std::fs::read_to_string(file) {
+        Ok(text) => text,
+        Err(_) => return false,
+    };
+    text.lines()
+        .any(|line| match line.trim_start().strip_prefix("deviation") {
+            Some(rest) => match rest.chars().next() {
+                Some(c) => c.is_whitespace() || c == '"' || c == '\'',
+                None => true,
+            },
+            None => false,
+        })
+}
+
 fn find_embedded_module<'a>(
     modules: &'a EmbeddedModules,
     mod_name: &'a str,
@@ -7,7 +7,11 @@
 //! YANG instance data.
 
 use bitflags::bitflags;
-use std::ffi::CString;
+use std::any::Any;
+use std::collections::HashSet;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::io;
 use std::os::raw::{c_char, c_void};
 use std::os::unix::io::AsRawFd;
 use std::slice;
@@ -16,26 +20,58 @@ use std::sync::Arc;
 use crate::context::Context;
 use crate::error::{Error, Result};
 use crate::iter::{
-    Ancestors, MetadataList, NodeIterable, Set, Siblings, Traverse,
+    Ancestors, Attributes, MetadataList, NodeIterable, Set, Siblings, Traverse,
+    TraverseDepth,
+};
+use crate::schema::{
+    DataValue, DataValueType, SchemaIdentity, SchemaLeafType, SchemaModule,
+    SchemaNode, SchemaNodeKind, SchemaPathFormat,
 };
-use crate::schema::{DataValue, SchemaModule, SchemaNode, SchemaNodeKind};
 use crate::utils::*;
 use libyang2_sys as ffi;
 
 /// YANG data tree.
+///
+/// A data tree holds a reference to the context it was created with, which
+/// keeps the context alive for as long as the data tree exists, even if all
+/// other references to the context were dropped. When the data tree is
+/// dropped, its data nodes are always freed before that reference is released.
 #[derive(Debug)]
 pub struct DataTree {
+    // NOTE: the data nodes are freed in Drop::drop(), which runs before the
+    // context reference below is released.
     context: Arc<Context>,
     raw: *mut ffi::lyd_node,
+    owned: bool,
+    // Whether typed user data was attached to any of the data nodes.
+    user_data: bool,
 }
 
 /// YANG data node reference.
+///
+/// Data node references borrow their data tree, so they can't outlive it nor
+/// be stored alongside it. To keep track of a data node across borrows, store
+/// its [`path`](DataNodeRef::path) instead and resolve it again later using
+/// [`DataTree::relocate`].
 #[derive(Clone, Debug)]
 pub struct DataNodeRef<'a> {
     tree: &'a DataTree,
     raw: *mut ffi::lyd_node,
 }
 
+/// Wrapper comparing data trees by content.
+///
+/// `DataTree` doesn't implement [`PartialEq`], and the [`PartialEq`]
+/// implementation of [`DataNodeRef`] checks for identity (i.e. whether both
+/// references point to the same data node), which is cheap but usually not
+/// what's wanted when comparing whole data trees. This wrapper implements
+/// [`PartialEq`] and [`Eq`] by comparing all the nodes of both data trees
+/// recursively, which is equivalent to [`DataTree::equivalent`] with no
+/// options (the order of the nodes matters, and implicit default nodes are
+/// equal to explicit nodes with the same value).
+#[derive(Clone, Copy, Debug)]
+pub struct ByContent<'a>(pub &'a DataTree);
+
 /// The structure provides information about metadata of a data element. Such
 /// attributes must map to annotations as specified in RFC 7952. The only
 /// exception is the filter type (in NETCONF get operations) and edit-config's
@@ -48,6 +84,88 @@ pub struct Metadata<'a> {
     raw: *mut ffi::lyd_meta,
 }
 
+/// Generic attribute of an opaque data node. Unlike [`Metadata`], attributes
+/// don't map to YANG annotations and their values aren't resolved. They are
+/// used, for instance, to represent the NETCONF subtree filter attributes
+/// (`type`, `select`) or any other attribute unknown to the YANG context.
+#[derive(Clone, Debug)]
+pub struct Attribute<'a> {
+    dnode: &'a DataNodeRef<'a>,
+    raw: *mut ffi::lyd_attr,
+}
+
+/// Value of an anydata or anyxml data node, in its internal representation.
+#[derive(Clone, Debug)]
+pub enum DataAnyValue<'a> {
+    /// Data tree (first top-level sibling).
+    DataTree(DataNodeRef<'a>),
+    /// Plain string, escaped when printed in XML.
+    String(&'a str),
+    /// Serialized XML data.
+    Xml(&'a str),
+    /// Serialized JSON data.
+    Json(&'a str),
+    /// Serialized LYB data.
+    Lyb(&'a [u8]),
+}
+
+/// Type of a data node.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DataNodeType {
+    /// Data node with a schema definition of the given kind.
+    Schema(SchemaNodeKind),
+    /// Opaque data node, without a schema definition.
+    Opaque,
+}
+
+/// Summary of a data node, mostly useful for diagnostics (see
+/// [`DataNodeRef::describe`]).
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeDescription {
+    /// Path of the node.
+    pub path: String,
+    /// Type of the node.
+    pub node_type: DataNodeType,
+    /// Base type of the node's value (leafs and leaf-lists only).
+    pub value_type: Option<DataValueType>,
+    /// Name of the type as written in the schema, when available (see
+    /// [`SchemaLeafType::type_name`]).
+    pub type_name: Option<String>,
+    /// Node's value (canonical string representation).
+    pub value: Option<String>,
+    /// Whether the node value equals to its default one.
+    pub is_default: bool,
+}
+
+/// Incremental YANG data parser.
+///
+/// libyang can't parse data in a streaming fashion, so the input chunks are
+/// accumulated in an internal buffer which is parsed in place once all of
+/// them were fed. This avoids keeping both the complete input and a copy of it
+/// in memory, like when concatenating the chunks before calling
+/// [`DataTree::parse_string`].
+#[derive(Debug)]
+pub struct DataParser {
+    context: Arc<Context>,
+    format: DataFormat,
+    parser_options: DataParserFlags,
+    validation_options: DataValidationFlags,
+    buffer: Vec<u8>,
+}
+
+/// Builder composing the data trees of multiple modules into a single
+/// validated data tree.
+///
+/// Each data tree must only contain data of the module it's added for.
+/// Errors found while merging or validating the combined data are attributed
+/// to the module that contributed the offending data, whose name is prepended
+/// to the error message.
+#[derive(Debug)]
+pub struct DataStoreBuilder {
+    tree: DataTree,
+    modules: Vec<String>,
+}
+
 /// YANG data tree diff.
 #[derive(Debug)]
 pub struct DataDiff {
@@ -62,6 +180,141 @@ pub enum DataDiffOp {
     Replace,
 }
 
+/// NETCONF edit-config operation, as specified by the "operation" attribute
+/// (RFC 6241, section 7.2).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EditOperation {
+    Merge,
+    Replace,
+    Create,
+    Delete,
+    Remove,
+    None,
+}
+
+/// YANG Patch (RFC 8072): an ordered list of edits applied to a data tree as
+/// a whole.
+#[derive(Clone, Debug, Default)]
+pub struct YangPatch {
+    /// Identifier of the patch.
+    pub patch_id: String,
+    /// Optional description of the patch.
+    pub comment: Option<String>,
+    /// Edits, applied in order.
+    pub edits: Vec<YangPatchEdit>,
+}
+
+/// YANG Patch edit.
+#[derive(Clone, Debug)]
+pub struct YangPatchEdit {
+    /// Identifier of the edit, used to attribute errors.
+    pub edit_id: String,
+    /// Edit operation.
+    pub operation: YangPatchOperation,
+    /// Target data node, as a RESTCONF data resource identifier relative to
+    /// the patch target (e.g. `/interface=eth0`).
+    pub target: String,
+    /// Anchor list entry or leaf-list value for the `Before` and `After`
+    /// positions, relative to the patch target like the edit target.
+    pub point: Option<String>,
+    /// Position of the inserted or moved node (defaults to `Last`).
+    pub position: Option<YangPatchWhere>,
+    /// New value of the target data node, encoded in JSON (RFC 7951) with
+    /// the target node as the only member of the top-level object.
+    pub value: Option<String>,
+}
+
+/// YANG Patch edit operation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum YangPatchOperation {
+    Create,
+    Delete,
+    Insert,
+    Merge,
+    Move,
+    Replace,
+    Remove,
+}
+
+/// Position of the node inserted or moved by a YANG Patch edit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum YangPatchWhere {
+    Before,
+    After,
+    First,
+    Last,
+}
+
+/// Outcome of [`DataTree::new_or_update_path`].
+#[derive(Clone, Debug)]
+pub enum NewPathOutcome<'a> {
+    /// The node didn't exist and was created, along with any missing parent.
+    /// The last created node is returned.
+    Created(DataNodeRef<'a>),
+    /// The node already existed and its value (or default flag) was changed.
+    Updated(DataNodeRef<'a>),
+    /// The node already existed with the same value.
+    Unchanged,
+}
+
+/// Data diff change, including the values of the changed node before and
+/// after the change.
+#[derive(Clone, Debug)]
+pub struct DataDiffChange<'a> {
+    /// Diff operation.
+    pub op: DataDiffOp,
+    /// Changed data node.
+    pub node: DataNodeRef<'a>,
+    /// Whether the change concerns a default node (see [`DataDiff::iter`]).
+    pub is_default: bool,
+    /// Value before the change (leafs, leaf-lists and anydata/anyxml nodes,
+    /// whose content is serialized).
+    pub old_value: Option<String>,
+    /// Value after the change (leafs, leaf-lists and anydata/anyxml nodes,
+    /// whose content is serialized).
+    pub new_value: Option<String>,
+}
+
+/// Number of changes of each type in a data diff, as returned by
+/// [`DataDiff::summary`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DiffSummary {
+    /// Number of created nodes.
+    pub created: usize,
+    /// Number of deleted nodes.
+    pub deleted: usize,
+    /// Number of replaced nodes.
+    pub replaced: usize,
+}
+
+/// Element yielded by [`DataNodeRef::walk_with_context`], along with the
+/// context of its position in the data tree.
+#[derive(Clone, Debug)]
+pub struct WalkEvent<'a> {
+    /// Visited data node.
+    pub node: DataNodeRef<'a>,
+    /// Parent of the visited data node, if any.
+    pub parent: Option<DataNodeRef<'a>>,
+    /// Whether the visited data node is the last one among its siblings.
+    pub is_last: bool,
+}
+
+/// Value formats supported by [`DataNodeRef::value_formatted`]. They only
+/// differ in the prefixes used by identityref and instance-identifier values.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ValueFormat {
+    /// Canonical format, using module names as prefixes.
+    Canonical,
+    /// JSON format (RFC 7951), using module names as prefixes.
+    Json,
+    /// XML format (RFC 7950), using module prefixes. The corresponding XML
+    /// namespaces must be declared by the caller.
+    Xml,
+    /// Format of the module where the node is defined, using the prefixes
+    /// of the module itself and of its imports.
+    SchemaName,
+}
+
 /// Data input/output formats supported by libyang.
 #[allow(clippy::upper_case_acronyms)]
 #[repr(u32)]
@@ -128,6 +381,24 @@ bitflags! {
         const STRICT = ffi::LYD_PARSE_STRICT;
         /// Forbid state data in the parsed data.
         const NO_STATE = ffi::LYD_PARSE_NO_STATE;
+        /// Mark all the parsed nodes dependent on a when to be true. Useful
+        /// when re-parsing data that were validated before (e.g. stored
+        /// operational state), whose when conditions may no longer be
+        /// resolvable.
+        const WHEN_TRUE = ffi::LYD_PARSE_WHEN_TRUE;
+        /// Expect the data to be in the schema-defined order (list keys first,
+        /// followed by the other nodes as defined in the schema), which is
+        /// always the case for data printed by libyang and not modified
+        /// afterwards. This allows the parser to skip looking for the right
+        /// place of each parsed node. No error is raised for unordered data:
+        /// libyang documents the behavior as undefined in that case, and the
+        /// resulting data tree can't be expected to work correctly.
+        const ORDERED = ffi::LYD_PARSE_ORDERED;
+        /// Instead of failing, parse data with invalid values or without
+        /// schema definition into opaque nodes. Only meant to be used along
+        /// with [`DataParserFlags::NO_VALIDATION`], since opaque nodes can't
+        /// be validated.
+        const OPAQ = ffi::LYD_PARSE_OPAQ;
     }
 }
 
@@ -141,19 +412,29 @@ bitflags! {
         const NO_STATE = ffi::LYD_VALIDATE_NO_STATE;
         /// Validate only modules whose data actually exist.
         const PRESENT = ffi::LYD_VALIDATE_PRESENT;
+        /// Do not add any default nodes during validation, leaving the data
+        /// exactly as provided (other implicit nodes, like non-presence
+        /// containers, are still added). Validation fails if a default node
+        /// is required for it to pass.
+        const NO_DEFAULTS = ffi::LYD_VALIDATE_NO_DEFAULTS;
     }
 }
 
 bitflags! {
     /// Data printer flags.
     ///
-    /// Various options to change data validation behaviour, both for the parser
-    /// and separate validation.
+    /// Various options to change the data tree printers behavior.
+    ///
+    /// Unless the [`DataPrinterFlags::SHRINK`] flag is used, the XML and JSON
+    /// outputs are formatted with new lines and an indentation of two spaces
+    /// per nesting level. libyang doesn't support changing the indentation
+    /// width.
     pub struct DataPrinterFlags: u32 {
         /// Flag for printing also the (following) sibling nodes of the data
         /// node.
         const WITH_SIBLINGS = ffi::LYD_PRINT_WITHSIBLINGS;
-        /// Flag for output without indentation and formatting new lines.
+        /// Flag for output without indentation and formatting new lines. This
+        /// produces the most compact output (e.g. `{"a:b":{"c":1}}` in JSON).
         const SHRINK = ffi::LYD_PRINT_SHRINK;
         /// Preserve empty non-presence containers.
         const KEEP_EMPTY_CONT = ffi::LYD_PRINT_KEEPEMPTYCONT;
@@ -204,6 +485,23 @@ bitflags! {
     }
 }
 
+bitflags! {
+    /// Data compare options.
+    ///
+    /// Default behavior:
+    /// - the default flag of the nodes is ignored, only their values are
+    ///   compared.
+    /// - the relative order of the sibling nodes is significant (see
+    ///   [`DataTree::equivalent_unordered`] otherwise).
+    pub struct DataCompareFlags: u32 {
+        /// Default nodes are not considered equal to explicit nodes, even if
+        /// their values are the same.
+        const DEFAULTS = ffi::LYD_COMPARE_DEFAULTS;
+        /// Opaque nodes can match data nodes with the same name and value.
+        const OPAQ = ffi::LYD_COMPARE_OPAQ;
+    }
+}
+
 /// Methods common to data trees, data node references and data diffs.
 pub trait Data {
     #[doc(hidden)]
@@ -230,7 +528,11 @@ pub trait Data {
     /// the form `leaf-list[.='val']`, these instances are found using hashes
     /// with constant (*O(1)*) complexity (unless they are defined in
     /// top-level). Other predicates can still follow the aforementioned ones.
+    ///
+    /// An error is returned without calling into libyang if the first node of
+    /// an absolute expression isn't prefixed by its module name.
     fn find_xpath(&self, xpath: &str) -> Result<Set<'_, DataNodeRef<'_>>> {
+        check_json_xpath(xpath)?;
         let xpath = CString::new(xpath).unwrap();
         let mut set = std::ptr::null_mut();
         let set_ptr = &mut set;
@@ -252,18 +554,38 @@ pub trait Data {
         Ok(Set::new(self.tree(), slice))
     }
 
+    /// Search in the given data for instances of nodes matching the provided
+    /// XPath and return their values (canonical string representation).
+    ///
+    /// Nodes without a value (e.g. containers) are skipped. See
+    /// [`Data::find_xpath`] for the expected format of the expression.
+    fn collect_values(&self, xpath: &str) -> Result<Vec<String>> {
+        Ok(self.find_xpath(xpath)?.values().flatten().collect())
+    }
+
     /// Search in the given data for a single node matching the provided XPath.
     ///
     /// The expected format of the expression is JSON, meaning the first node in
     /// every path must have its module name as prefix or be the special `*`
     /// value for all the nodes.
-    fn find_path(&self, path: &str) -> Result<DataNodeRef<'_>> {
+    ///
+    /// RPCs and actions have separate input and output data nodes, which may
+    /// even share the same names. By default the path is resolved against the
+    /// input nodes, as found in a request. Set `output` to true to resolve it
+    /// against the output nodes instead, as found in a reply.
+    fn find_path(&self, path: &str, output: bool) -> Result<DataNodeRef<'_>> {
+        check_json_xpath(path)?;
         let path = CString::new(path).unwrap();
         let mut rnode = std::ptr::null_mut();
         let rnode_ptr = &mut rnode;
 
         let ret = unsafe {
-            ffi::lyd_find_path(self.raw(), path.as_ptr(), 0u8, rnode_ptr)
+            ffi::lyd_find_path(
+                self.raw(),
+                path.as_ptr(),
+                output as u8,
+                rnode_ptr,
+            )
         };
         if ret != ffi::LY_ERR::LY_SUCCESS {
             return Err(Error::new(self.context()));
@@ -272,6 +594,23 @@ pub trait Data {
         Ok(unsafe { DataNodeRef::from_raw(self.tree(), rnode as *mut _) })
     }
 
+    /// Returns an iterator over all nodes flagged as default (depth-first
+    /// search algorithm).
+    ///
+    /// The default flag is set by libyang on the nodes it creates implicitly
+    /// (e.g. default values added during validation) and on the nodes parsed
+    /// with the `ietf-netconf-with-defaults:default` metadata. Unlike
+    /// [`DataNodeRef::is_default`], explicit nodes whose value happens to
+    /// equal the default one aren't flagged.
+    fn default_nodes(&self) -> impl Iterator<Item = DataNodeRef<'_>> {
+        let top = unsafe { DataNodeRef::from_raw_opt(self.tree(), self.raw()) };
+        Siblings::new(top)
+            .flat_map(|dnode| dnode.traverse())
+            .filter(
+                |dnode| unsafe { (*dnode.raw).flags } & ffi::LYD_DEFAULT != 0,
+            )
+    }
+
     /// Print data tree in the specified format.
     fn print_file<F: AsRawFd>(
         &self,
@@ -317,6 +656,171 @@ pub trait Data {
 
         Ok(char_ptr_to_opt_string(cstr))
     }
+
+    /// Print data tree in the specified format, returning the raw bytes.
+    ///
+    /// Unlike [`Data::print_string`], this also supports the binary LYB
+    /// format. LYB data embeds a hash of the modules of the context it was
+    /// printed with, which can be checked using [`Context::lyb_compatible`]
+    /// before parsing it.
+    fn print_bytes(
+        &self,
+        format: DataFormat,
+        options: DataPrinterFlags,
+    ) -> Result<Vec<u8>> {
+        let mut cstr = std::ptr::null_mut();
+        let cstr_ptr = &mut cstr;
+
+        let ret = unsafe {
+            ffi::lyd_print_mem(
+                cstr_ptr,
+                self.raw(),
+                format as u32,
+                options.bits(),
+            )
+        };
+        if ret != ffi::LY_ERR::LY_SUCCESS {
+            return Err(Error::new(self.context()));
+        }
+        if cstr.is_null() {
+            return Ok(Vec::new());
+        }
+
+        let len = match format {
+            DataFormat::LYB => unsafe { ffi::lyd_lyb_data_length(cstr) },
+            _ => unsafe { CStr::from_ptr(cstr) }.to_bytes().len() as i32,
+        };
+        let data = if len > 0 {
+            unsafe { slice::from_raw_parts(cstr as *const u8, len as usize) }
+                .to_vec()
+        } else {
+            Vec::new()
+        };
+        unsafe { ffi::free(cstr as *mut c_void) };
+
+        Ok(data)
+    }
+
+    /// Print data tree in the specified format, passing the output to the
+    /// given callback in chunks as it's produced, without ever holding the
+    /// complete output in memory.
+    ///
+    /// Printing is aborted as soon as the callback returns an error, which is
+    /// then returned as a [`ffi::LY_ERR::LY_ESYS`] error.
+    fn print_chunks<F>(
+        &self,
+        format: DataFormat,
+        options: DataPrinterFlags,
+        mut f: F,
+    ) -> Result<()>
+    where
+        F: FnMut(&[u8]) -> io::Result<()>,
+    {
+        let mut state = PrintChunks {
+            f: &mut f,
+            error: None,
+        };
+
+        let ret = unsafe {
+            ffi::lyd_print_clb(
+                Some(ly_write_chunk_cb),
+                &mut state as *mut _ as *mut c_void,
+                self.raw(),
+                format as u32,
+                options.bits(),
+            )
+        };
+        if let Some(error) = state.error {
+            return Err(Error::with_msg(
+                ffi::LY_ERR::LY_ESYS,
+                error.to_string(),
+                None,
+            ));
+        }
+        if ret != ffi::LY_ERR::LY_SUCCESS {
+            return Err(Error::new(self.context()));
+        }
+
+        Ok(())
+    }
+
+    /// Print data following the RESTCONF (RFC 8040) conventions.
+    ///
+    /// When `wrap` is false, only this node is printed (without its siblings),
+    /// as expected in the representation of a single data resource. An error
+    /// is returned if there's no such node.
+    ///
+    /// When `wrap` is true, this node and all its siblings are enclosed in the
+    /// "ietf-restconf:data" object (JSON) or the `data` element in the
+    /// ietf-restconf namespace (XML), as expected in the representation of a
+    /// datastore resource.
+    ///
+    /// The [`DataPrinterFlags::WITH_SIBLINGS`] option is ignored. Only the XML
+    /// and JSON formats are supported.
+    fn print_restconf(
+        &self,
+        format: DataFormat,
+        options: DataPrinterFlags,
+        wrap: bool,
+    ) -> Result<String> {
+        if format == DataFormat::LYB {
+            return Err(Error::with_msg(
+                ffi::LY_ERR::LY_EINVAL,
+                "RESTCONF data must be in XML or JSON",
+                None,
+            ));
+        }
+
+        let mut options = options - DataPrinterFlags::WITH_SIBLINGS;
+        let mut raw = self.raw();
+        if wrap {
+            if !raw.is_null() {
+                raw = unsafe { ffi::lyd_first_sibling(raw) };
+            }
+            options |= DataPrinterFlags::WITH_SIBLINGS;
+        } else if raw.is_null() {
+            return Err(Error::with_msg(
+                ffi::LY_ERR::LY_ENOTFOUND,
+                "No data resource to print",
+                None,
+            ));
+        }
+
+        let mut cstr = std::ptr::null_mut();
+        let cstr_ptr = &mut cstr;
+
+        let ret = unsafe {
+            ffi::lyd_print_mem(cstr_ptr, raw, format as u32, options.bits())
+        };
+        if ret != ffi::LY_ERR::LY_SUCCESS {
+            return Err(Error::new(self.context()));
+        }
+
+        let data = char_ptr_to_opt_string(cstr).unwrap_or_default();
+        unsafe { ffi::free(cstr as *mut c_void) };
+        if !wrap {
+            return Ok(data);
+        }
+
+        let shrink = options.contains(DataPrinterFlags::SHRINK);
+        Ok(restconf_wrap(&data, format, shrink))
+    }
+}
+
+/// Callbacks invoked while walking over a data tree using
+/// [`DataNodeRef::walk`].
+///
+/// Unlike the flat iterators, the walk reports when each node is entered and
+/// left, which allows to rebuild the structure of the tree (e.g. to serialize
+/// it to a nested format).
+pub trait DataVisitor<'a> {
+    /// Called when a data node is entered, before its children are visited.
+    /// The depth is relative to the node where the walk started, which has
+    /// depth zero.
+    fn enter(&mut self, _dnode: &DataNodeRef<'a>, _depth: usize) {}
+
+    /// Called when a data node is left, after all its children were visited.
+    fn leave(&mut self, _dnode: &DataNodeRef<'a>, _depth: usize) {}
 }
 
 // ===== impl DataTree =====
@@ -327,10 +831,41 @@ impl DataTree {
         DataTree {
             context: context.clone(),
             raw: std::ptr::null_mut(),
+            owned: true,
+            user_data: false,
+        }
+    }
+
+    /// Create a non-owning data tree from a raw data node owned by another
+    /// library (e.g. sysrepo). The data nodes aren't freed when the returned
+    /// data tree is dropped.
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that the provided pointer is either null or a
+    /// valid first top-level sibling created using the given context, and that
+    /// it remains valid for as long as the returned data tree is in use. If
+    /// typed user data (see [`DataTree::set_user_data`]) is attached to the
+    /// data nodes, their private pointers must not be used by the owner of
+    /// the data tree.
+    pub unsafe fn borrow_raw(
+        context: &Arc<Context>,
+        raw: *mut ffi::lyd_node,
+    ) -> DataTree {
+        DataTree {
+            context: context.clone(),
+            raw,
+            owned: false,
+            user_data: false,
         }
     }
 
     /// Parse (and validate) input data as a YANG data tree.
+    ///
+    /// libyang doesn't keep track of the input position of the parsed data
+    /// nodes (regardless of the parser options), so their source line isn't
+    /// available once parsing succeeds. Parsing errors, however, include the
+    /// line number of the offending input in their path.
     pub fn parse_file<F: AsRawFd>(
         context: &Arc<Context>,
         fd: F,
@@ -387,57 +922,306 @@ impl DataTree {
         Ok(unsafe { DataTree::from_raw(context, rnode) })
     }
 
-    /// Parse YANG data into an operation data tree.
-    pub fn parse_op_string(
+    /// Parse (and validate) input data as a YANG data tree, failing if the
+    /// resulting data tree has more than `max_nodes` nodes (see
+    /// [`DataTree::node_count`]).
+    ///
+    /// libyang can't abort parsing midway, so the limit is checked once the
+    /// whole input is parsed, and the data tree is freed if it's exceeded.
+    /// This bounds the memory retained per parsed document, but not the
+    /// memory used while parsing it, so the size of the input should be
+    /// limited as well.
+    pub fn parse_string_limited(
         context: &Arc<Context>,
         data: &str,
         format: DataFormat,
-        op: DataOperation,
+        parser_options: DataParserFlags,
+        validation_options: DataValidationFlags,
+        max_nodes: usize,
     ) -> Result<DataTree> {
-        let mut rnode = std::ptr::null_mut();
-        let rnode_ptr = &mut rnode;
-
-        // Create input handler.
-        let data = CString::new(data).unwrap();
-        let mut ly_in = std::ptr::null_mut();
-        let ret = unsafe { ffi::ly_in_new_memory(data.as_ptr(), &mut ly_in) };
-        if ret != ffi::LY_ERR::LY_SUCCESS {
-            return Err(Error::new(context));
+        let dtree = DataTree::parse_string(
+            context,
+            data,
+            format,
+            parser_options,
+            validation_options,
+        )?;
+        if dtree.traverse().nth(max_nodes).is_some() {
+            return Err(Error::with_msg(
+                ffi::LY_ERR::LY_EINVAL,
+                format!("Data tree exceeds the maximum of {} nodes", max_nodes),
+                None,
+            ));
         }
 
-        let ret = unsafe {
-            ffi::lyd_parse_op(
-                context.raw,
-                std::ptr::null_mut(),
-                ly_in,
-                format as u32,
-                op as u32,
-                rnode_ptr,
-                std::ptr::null_mut(),
-            )
+        Ok(dtree)
+    }
+
+    /// Parse input data as a YANG data tree and return a copy of the single
+    /// node matching the provided XPath, along with its descendants.
+    ///
+    /// The parsed data isn't validated, and it's freed before returning. The
+    /// matching node is the first top-level node of the returned data tree,
+    /// so its value can be read using
+    /// `reference().and_then(|dnode| dnode.value_canonical())`. See
+    /// [`Data::find_path`] for the expected format of the expression.
+    pub fn parse_and_find(
+        context: &Arc<Context>,
+        data: &str,
+        format: DataFormat,
+        xpath: &str,
+    ) -> Result<DataTree> {
+        let dtree = DataTree::parse_string(
+            context,
+            data,
+            format,
+            DataParserFlags::NO_VALIDATION,
+            DataValidationFlags::empty(),
+        )?;
+        let dnode = dtree.find_path(xpath, false)?;
+        dnode.duplicate(false)
+    }
+
+    /// Parse a JSON value as a YANG data tree.
+    ///
+    /// The value is serialized and parsed using the JSON format, so it must
+    /// follow the JSON encoding of YANG data (RFC 7951).
+    #[cfg(feature = "serde_json")]
+    pub fn from_json_value(
+        context: &Arc<Context>,
+        value: &serde_json::Value,
+        parser_options: DataParserFlags,
+        validation_options: DataValidationFlags,
+    ) -> Result<DataTree> {
+        DataTree::parse_string(
+            context,
+            &value.to_string(),
+            DataFormat::JSON,
+            parser_options,
+            validation_options,
+        )
+    }
+
+    /// Convert the data tree and its sibling trees to a JSON value, using the
+    /// JSON encoding of YANG data (RFC 7951).
+    #[cfg(feature = "serde_json")]
+    pub fn to_json_value(&self) -> Result<serde_json::Value> {
+        let data = self
+            .print_string(DataFormat::JSON, DataPrinterFlags::WITH_SIBLINGS)?;
+        let data = match data {
+            Some(data) => data,
+            None => return Ok(serde_json::Value::Object(Default::default())),
         };
-        if ret != ffi::LY_ERR::LY_SUCCESS {
-            return Err(Error::new(context));
-        }
 
-        Ok(unsafe { DataTree::from_raw(context, rnode) })
+        serde_json::from_str(&data).map_err(|error| {
+            Error::with_msg(
+                ffi::LY_ERR::LY_EINVAL,
+                format!("Failed to convert printed data: {}", error),
+                None,
+            )
+        })
     }
 
-    /// Returns a reference to the fist top-level data node, unless the data
-    /// tree is empty.
-    pub fn reference(&self) -> Option<DataNodeRef<'_>> {
-        if self.raw.is_null() {
-            None
-        } else {
-            Some(DataNodeRef {
-                tree: self,
-                raw: self.raw,
+    /// Parse input data as a YANG data tree, continuing past invalid values
+    /// and unknown nodes instead of aborting.
+    ///
+    /// The invalid and unknown nodes are kept in the returned data tree as
+    /// opaque nodes, and an error describing why each of them couldn't be
+    /// parsed is returned alongside it. The data tree isn't validated.
+    /// Malformed input (e.g. a syntax error) still makes the parsing fail.
+    pub fn parse_lenient(
+        context: &Arc<Context>,
+        data: &str,
+        format: DataFormat,
+        parser_options: DataParserFlags,
+    ) -> Result<(DataTree, Vec<Error>)> {
+        let parser_options = (parser_options - DataParserFlags::STRICT)
+            | DataParserFlags::NO_VALIDATION
+            | DataParserFlags::OPAQ;
+        let dtree = DataTree::parse_string(
+            context,
+            data,
+            format,
+            parser_options,
+            DataValidationFlags::empty(),
+        )?;
+
+        // Report only the topmost opaque nodes, since their descendants are
+        // opaque as well.
+        let is_opaq =
+            |dnode: &DataNodeRef<'_>| unsafe { (*dnode.raw).schema }.is_null();
+        let errors = dtree
+            .traverse()
+            .filter(|dnode| {
+                is_opaq(dnode) && dnode.parent().filter(is_opaq).is_none()
             })
-        }
+            .filter_map(|dnode| {
+                let ret = unsafe { ffi::lyd_parse_opaq_error(dnode.raw) };
+                if ret == ffi::LY_ERR::LY_SUCCESS {
+                    return None;
+                }
+                Some(Error::new(context))
+            })
+            .collect();
+
+        Ok((dtree, errors))
     }
 
-    /// Create a new node or modify existing one in the data tree based on a
-    /// path.
+    /// Parse (and validate) input data from a byte buffer as a YANG data tree.
+    ///
+    /// The buffer is only read during parsing: the resulting data tree
+    /// doesn't reference it, so it can be released (e.g. unmapped) right
+    /// after this function returns. libyang always builds its own copy of
+    /// the data nodes and values, regardless of the input format.
+    ///
+    /// XML and JSON data must be null-terminated, so the buffer is copied
+    /// unless its last byte is already a null byte. LYB data isn't accepted,
+    /// since libyang reads it without bounds checking: use
+    /// [`DataTree::parse_lyb`] instead.
+    pub fn parse_bytes(
+        context: &Arc<Context>,
+        data: &[u8],
+        format: DataFormat,
+        parser_options: DataParserFlags,
+        validation_options: DataValidationFlags,
+    ) -> Result<DataTree> {
+        if format == DataFormat::LYB {
+            return Err(Error::with_msg(
+                ffi::LY_ERR::LY_EINVAL,
+                "LYB data must be parsed using DataTree::parse_lyb",
+                None,
+            ));
+        }
+
+        let data_cstr;
+        let data_ptr = match CStr::from_bytes_with_nul(data) {
+            Ok(data) => data.as_ptr(),
+            Err(_) => {
+                data_cstr = CString::new(data).map_err(|_| {
+                    Error::with_msg(
+                        ffi::LY_ERR::LY_EINVAL,
+                        "Input data contains a null byte",
+                        None,
+                    )
+                })?;
+                data_cstr.as_ptr()
+            }
+        };
+
+        parse_data_mem(
+            context,
+            data_ptr,
+            format,
+            parser_options,
+            validation_options,
+        )
+    }
+
+    /// Parse (and validate) LYB data from a byte buffer as a YANG data tree.
+    ///
+    /// The buffer is parsed in place, without copying it, and isn't
+    /// referenced by the resulting data tree. Buffers too short to hold a LYB
+    /// header or not starting with the LYB magic number are rejected.
+    ///
+    /// # Safety
+    ///
+    /// libyang reads LYB data without knowing the length of the buffer, so
+    /// the caller must ensure that it holds a complete LYB document (e.g.
+    /// printed using [`Data::print_bytes`] and stored unmodified). Truncated
+    /// or corrupted data can cause reads past the end of the buffer.
+    pub unsafe fn parse_lyb(
+        context: &Arc<Context>,
+        data: &[u8],
+        parser_options: DataParserFlags,
+        validation_options: DataValidationFlags,
+    ) -> Result<DataTree> {
+        check_lyb_header(data)?;
+
+        parse_data_mem(
+            context,
+            data.as_ptr() as *const c_char,
+            DataFormat::LYB,
+            parser_options,
+            validation_options,
+        )
+    }
+
+    /// Parse input data as a YANG data tree without validating it.
+    ///
+    /// This is a preset for read-only paths that handle large amounts of data
+    /// already known to be valid (e.g. telemetry ingestion). It's equivalent
+    /// to calling [`DataTree::parse_string`] with the
+    /// [`DataParserFlags::NO_VALIDATION`] parser option. No default values
+    /// are added to the resulting tree.
+    ///
+    /// Data printed by libyang can be parsed even faster by adding the
+    /// [`DataParserFlags::ORDERED`] option, which isn't part of this preset
+    /// since unordered input leads to undefined behavior.
+    pub fn parse_fast(
+        context: &Arc<Context>,
+        data: &str,
+        format: DataFormat,
+    ) -> Result<DataTree> {
+        DataTree::parse_string(
+            context,
+            data,
+            format,
+            DataParserFlags::NO_VALIDATION,
+            DataValidationFlags::empty(),
+        )
+    }
+
+    /// Parse YANG data into an operation data tree.
+    pub fn parse_op_string(
+        context: &Arc<Context>,
+        data: &str,
+        format: DataFormat,
+        op: DataOperation,
+    ) -> Result<DataTree> {
+        let mut rnode = std::ptr::null_mut();
+        let rnode_ptr = &mut rnode;
+
+        // Create input handler.
+        let data = CString::new(data).unwrap();
+        let mut ly_in = std::ptr::null_mut();
+        let ret = unsafe { ffi::ly_in_new_memory(data.as_ptr(), &mut ly_in) };
+        if ret != ffi::LY_ERR::LY_SUCCESS {
+            return Err(Error::new(context));
+        }
+
+        let ret = unsafe {
+            ffi::lyd_parse_op(
+                context.raw,
+                std::ptr::null_mut(),
+                ly_in,
+                format as u32,
+                op as u32,
+                rnode_ptr,
+                std::ptr::null_mut(),
+            )
+        };
+        if ret != ffi::LY_ERR::LY_SUCCESS {
+            return Err(Error::new(context));
+        }
+
+        Ok(unsafe { DataTree::from_raw(context, rnode) })
+    }
+
+    /// Returns a reference to the fist top-level data node, unless the data
+    /// tree is empty.
+    pub fn reference(&self) -> Option<DataNodeRef<'_>> {
+        if self.raw.is_null() {
+            None
+        } else {
+            Some(DataNodeRef {
+                tree: self,
+                raw: self.raw,
+            })
+        }
+    }
+
+    /// Create a new node or modify existing one in the data tree based on a
+    /// path.
     ///
     /// If path points to a list key and the list instance does not exist,
     /// the key value from the predicate is used and value is ignored. Also,
@@ -457,61 +1241,544 @@ impl DataTree {
         value: Option<&str>,
         output: bool,
     ) -> Result<Option<DataNodeRef<'_>>> {
-        let path = CString::new(path).unwrap();
-        let mut rnode_root = std::ptr::null_mut();
-        let mut rnode = std::ptr::null_mut();
-        let rnode_root_ptr = &mut rnode_root;
-        let rnode_ptr = &mut rnode;
-        let value_cstr;
+        let mut options = ffi::LYD_NEW_PATH_UPDATE;
+        if output {
+            options |= ffi::LYD_NEW_PATH_OUTPUT;
+        }
 
-        let (value_ptr, value_len) = match value {
-            Some(value) => {
-                value_cstr = CString::new(value).unwrap();
-                (value_cstr.as_ptr(), value.len())
+        match self.new_path_options(path, value, options)? {
+            NewPathOutcome::Created(dnode) | NewPathOutcome::Updated(dnode) => {
+                Ok(Some(dnode))
             }
-            None => (std::ptr::null(), 0),
-        };
+            NewPathOutcome::Unchanged => Ok(None),
+        }
+    }
 
+    /// Create a new node or modify existing one in the data tree based on a
+    /// path, like [`DataTree::new_path`], reporting whether the node was
+    /// created, updated or left unchanged.
+    pub fn new_or_update_path(
+        &mut self,
+        path: &str,
+        value: Option<&str>,
+        output: bool,
+    ) -> Result<NewPathOutcome<'_>> {
         let mut options = ffi::LYD_NEW_PATH_UPDATE;
         if output {
             options |= ffi::LYD_NEW_PATH_OUTPUT;
         }
 
+        self.new_path_options(path, value, options)
+    }
+
+    /// Create a skeleton data tree for the given module, to be used as a
+    /// fill-in-the-blanks starting configuration.
+    ///
+    /// All non-presence configuration containers of the module are
+    /// instantiated, along with the mandatory configuration leaves inside
+    /// them. Mandatory leaves are created with an empty value, or as opaque
+    /// nodes when an empty value isn't valid for their type, so the returned
+    /// tree doesn't pass validation until all of them are filled in. Presence
+    /// containers, lists, choices and optional leaves are left out.
+    pub fn new_skeleton(
+        context: &Arc<Context>,
+        module: &SchemaModule<'_>,
+    ) -> Result<DataTree> {
+        let mut dtree = DataTree::new(context);
+        let mut snodes = module.data().collect::<Vec<_>>();
+        while let Some(snode) = snodes.pop() {
+            if !snode.is_config() {
+                continue;
+            }
+
+            let path = snode.path(SchemaPathFormat::DATA);
+            match snode.kind() {
+                SchemaNodeKind::Container if snode.is_np_container() => {
+                    dtree.new_path_options(
+                        &path,
+                        None,
+                        ffi::LYD_NEW_PATH_UPDATE,
+                    )?;
+                    snodes.extend(snode.children());
+                }
+                SchemaNodeKind::Leaf if snode.is_mandatory() => {
+                    dtree.new_path_options(
+                        &path,
+                        Some(""),
+                        ffi::LYD_NEW_PATH_UPDATE | ffi::LYD_NEW_PATH_OPAQ,
+                    )?;
+                }
+                _ => (),
+            }
+        }
+
+        Ok(dtree)
+    }
+
+    /// Create a new opaque node. Opaque nodes have no schema and are
+    /// identified only by their name and the XML namespace of their module.
+    /// Their value, if any, isn't resolved.
+    ///
+    /// The node is created as a child of the node identified by the given
+    /// parent path, which must be an opaque node itself, or as a top-level
+    /// node if no parent is given.
+    pub fn new_opaq(
+        &mut self,
+        parent: Option<&str>,
+        name: &str,
+        value: Option<&str>,
+        module_ns: &str,
+    ) -> Result<DataNodeRef<'_>> {
+        let rparent = match parent {
+            Some(path) => self.find_node(path)?.raw,
+            None => std::ptr::null_mut(),
+        };
+        let rnode = new_opaq(self.context(), rparent, name, value, module_ns)?;
+
+        // Insert the new node as a top-level sibling.
+        if rparent.is_null() {
+            if self.raw.is_null() {
+                self.raw = rnode;
+            } else {
+                let mut first = std::ptr::null_mut();
+                let ret = unsafe {
+                    ffi::lyd_insert_sibling(self.raw, rnode, &mut first)
+                };
+                if ret != ffi::LY_ERR::LY_SUCCESS {
+                    unsafe { ffi::lyd_free_tree(rnode) };
+                    return Err(Error::new(self.context()));
+                }
+                self.raw = first;
+            }
+        }
+
+        Ok(unsafe { DataNodeRef::from_raw(self, rnode) })
+    }
+
+    /// Create multiple entries of a leaf-list at once, in the order of the
+    /// given values.
+    ///
+    /// The leaf-list entries are created as children of the node identified
+    /// by the given parent path, or as top-level nodes if no parent is given.
+    /// The module is required for top-level leaf-lists and for leaf-lists
+    /// augmented by a module other than the one of the parent node.
+    ///
+    /// Each value is validated against the leaf-list type. If any of them is
+    /// invalid, none of the entries is created.
+    pub fn new_leaflist(
+        &mut self,
+        parent: Option<&str>,
+        module: Option<&str>,
+        name: &str,
+        values: &[&str],
+    ) -> Result<()> {
+        let rparent = match parent {
+            Some(path) => self.find_path(path, false)?.raw,
+            None => std::ptr::null_mut(),
+        };
+        let rmodule = match module {
+            Some(module) => {
+                let module_cstr = CString::new(module).unwrap();
+                let rmodule = unsafe {
+                    ffi::ly_ctx_get_module_implemented(
+                        self.context.raw,
+                        module_cstr.as_ptr(),
+                    )
+                };
+                if rmodule.is_null() {
+                    return Err(Error::with_msg(
+                        ffi::LY_ERR::LY_EINVAL,
+                        format!("Implemented module \"{}\" not found", module),
+                        None,
+                    ));
+                }
+                rmodule
+            }
+            None => std::ptr::null_mut(),
+        };
+        let name = CString::new(name).unwrap();
+
+        // Create all entries, freeing the ones already created in case of
+        // failure.
+        let mut rnodes = Vec::with_capacity(values.len());
+        for value in values {
+            let value = CString::new(*value).unwrap();
+            let mut rnode = std::ptr::null_mut();
+            let ret = unsafe {
+                ffi::lyd_new_term(
+                    rparent,
+                    rmodule,
+                    name.as_ptr(),
+                    value.as_ptr(),
+                    0,
+                    &mut rnode,
+                )
+            };
+            if ret != ffi::LY_ERR::LY_SUCCESS {
+                let error = Error::new(self.context());
+                for rnode in rnodes {
+                    unsafe { ffi::lyd_free_tree(rnode) };
+                }
+                return Err(error);
+            }
+            rnodes.push(rnode);
+        }
+
+        // Insert the new nodes as top-level siblings.
+        if rparent.is_null() {
+            let mut rnodes = rnodes.into_iter();
+            while let Some(rnode) = rnodes.next() {
+                let mut first = rnode;
+                if !self.raw.is_null() {
+                    let ret = unsafe {
+                        ffi::lyd_insert_sibling(self.raw, rnode, &mut first)
+                    };
+                    if ret != ffi::LY_ERR::LY_SUCCESS {
+                        let error = Error::new(self.context());
+                        unsafe { ffi::lyd_free_tree(rnode) };
+                        for rnode in rnodes {
+                            unsafe { ffi::lyd_free_tree(rnode) };
+                        }
+                        return Err(error);
+                    }
+                }
+                self.raw = first;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Resolve a path previously generated by [`DataNodeRef::path`], returning
+    /// the data node it identifies, if it still exists.
+    pub fn relocate(&self, path: &str) -> Option<DataNodeRef<'_>> {
+        self.find_node(path).ok()
+    }
+
+    /// Remove a data node.
+    pub fn remove(&mut self, path: &str) -> Result<()> {
+        let raw = self.find_path(path, false)?.raw;
+        self.free_subtree(raw);
+        Ok(())
+    }
+
+    /// Attach typed user data to the data node identified by the given path,
+    /// replacing (and dropping) the user data previously attached to it, if
+    /// any.
+    ///
+    /// The user data is owned by the node and is dropped when the node is
+    /// removed using [`DataTree::remove`] or when the (owning) data tree is
+    /// dropped. libyang has no callback for freed data nodes, so the user
+    /// data of nodes freed by other means (e.g. implicitly by libyang) is
+    /// leaked. It can be retrieved using [`DataNodeRef::user_data`].
+    ///
+    /// The user data is stored in the private pointer of the node, so this
+    /// can't be mixed with [`DataNodeRef::set_private`]. An error is returned
+    /// if the private pointer of any node of the data tree is already in use
+    /// when typed user data is attached for the first time.
+    pub fn set_user_data<T: Any + Send + Sync>(
+        &mut self,
+        path: &str,
+        data: T,
+    ) -> Result<()> {
+        let raw = self.find_node(path)?.raw;
+        if !self.user_data {
+            if self.traverse().any(|dnode| dnode.get_private().is_some()) {
+                return Err(Error::with_msg(
+                    ffi::LY_ERR::LY_EEXIST,
+                    "Private pointer of data node already in use",
+                    Some(path.to_owned()),
+                ));
+            }
+            self.user_data = true;
+        }
+
+        let data: Box<UserData> = Box::new(Box::new(data));
+        unsafe {
+            free_user_data(raw);
+            (*raw).priv_ = Box::into_raw(data) as *mut c_void;
+        }
+        Ok(())
+    }
+
+    /// Remove (and drop) the user data attached to the data node identified
+    /// by the given path, if any.
+    pub fn clear_user_data(&mut self, path: &str) -> Result<()> {
+        let raw = self.find_node(path)?.raw;
+        if self.user_data {
+            unsafe { free_user_data(raw) };
+        }
+        Ok(())
+    }
+
+    /// Copy all metadata of the given node to the data node identified by the
+    /// given path.
+    ///
+    /// Metadata already present in the target node is replaced by the
+    /// metadata of the same annotation in the source node. Metadata that
+    /// doesn't apply to the target node is skipped, namely all metadata when
+    /// the target node is opaque and metadata whose annotation belongs to a
+    /// different context.
+    pub fn copy_meta_from(
+        &mut self,
+        path: &str,
+        source: &DataNodeRef<'_>,
+    ) -> Result<()> {
+        let raw = self.find_node(path)?.raw;
+        if raw == source.raw || unsafe { (*raw).schema }.is_null() {
+            return Ok(());
+        }
+
+        let mut rmeta = unsafe { (*source.raw).meta };
+        while !rmeta.is_null() {
+            let annotation = unsafe { (*rmeta).annotation };
+            if unsafe { (*(*annotation).module).ctx } == self.context.raw {
+                // Remove the existing instance of the annotation, if any.
+                let mut rold = unsafe { (*raw).meta };
+                while !rold.is_null() {
+                    if unsafe { (*rold).annotation } == annotation {
+                        unsafe { ffi::lyd_free_meta_single(rold) };
+                        break;
+                    }
+                    rold = unsafe { (*rold).next };
+                }
+
+                let ret = unsafe {
+                    ffi::lyd_dup_meta_single(rmeta, raw, std::ptr::null_mut())
+                };
+                if ret != ffi::LY_ERR::LY_SUCCESS {
+                    return Err(Error::new(self.context()));
+                }
+            }
+            rmeta = unsafe { (*rmeta).next };
+        }
+
+        Ok(())
+    }
+
+    /// Change the value of the leaf(-list) node identified by the given path.
+    ///
+    /// The value is converted to its string representation and validated
+    /// against the node's type. Note that decimal64 values are represented as
+    /// [`DataValue::Other`], containing the value in decimal notation.
+    pub fn set_value_typed(
+        &mut self,
+        path: &str,
+        value: DataValue,
+    ) -> Result<()> {
+        let dnode = self.find_node(path)?;
+        match dnode.node_type() {
+            DataNodeType::Schema(SchemaNodeKind::Leaf)
+            | DataNodeType::Schema(SchemaNodeKind::LeafList) => (),
+            _ => {
+                return Err(Error::with_msg(
+                    ffi::LY_ERR::LY_EINVAL,
+                    "Only leafs and leaf-lists have values",
+                    Some(dnode.path()),
+                ))
+            }
+        }
+
+        let raw = dnode.raw;
+        let value = CString::new(value.to_string()).unwrap();
+        let ret = unsafe { ffi::lyd_change_term(raw, value.as_ptr()) };
+        match ret {
+            // LY_EEXIST and LY_ENOT are returned when the value didn't change.
+            ffi::LY_ERR::LY_SUCCESS
+            | ffi::LY_ERR::LY_EEXIST
+            | ffi::LY_ERR::LY_ENOT => Ok(()),
+            _ => Err(Error::new(self.context())),
+        }
+    }
+
+    /// Reset the value of the leaf identified by the given path to the
+    /// default value of its schema node, flagging it as a default node.
+    ///
+    /// Default nodes are printed or not depending on the "with-defaults" mode
+    /// of the printer (see [`DataPrinterFlags`]). An error is returned if the
+    /// node isn't a leaf or if it has no default value.
+    pub fn reset_to_default(&mut self, path: &str) -> Result<()> {
+        let dnode = self.find_node(path)?;
+        let invalid = |msg: &str| {
+            Error::with_msg(ffi::LY_ERR::LY_EINVAL, msg, Some(dnode.path()))
+        };
+
+        if dnode.node_type() != DataNodeType::Schema(SchemaNodeKind::Leaf) {
+            return Err(invalid("Only leafs can be reset to their default"));
+        }
+        let default = match dnode.schema().default_value_canonical() {
+            Some(default) => CString::new(default).unwrap(),
+            None => return Err(invalid("Leaf has no default value")),
+        };
+
+        let raw = dnode.raw;
+        let ret = unsafe { ffi::lyd_change_term(raw, default.as_ptr()) };
+        match ret {
+            // LY_EEXIST and LY_ENOT are returned when the value didn't change.
+            ffi::LY_ERR::LY_SUCCESS
+            | ffi::LY_ERR::LY_EEXIST
+            | ffi::LY_ERR::LY_ENOT => (),
+            _ => return Err(Error::new(self.context())),
+        }
+        unsafe { (*raw).flags |= ffi::LYD_DEFAULT };
+
+        // Like libyang, also mark the ancestor non-presence containers whose
+        // children are all default nodes as default ones.
+        for dnode in dnode.ancestors() {
+            if !dnode.schema().is_np_container()
+                || dnode.children().any(|dnode| {
+                    (unsafe { (*dnode.raw).flags } & ffi::LYD_DEFAULT) == 0
+                })
+            {
+                break;
+            }
+            unsafe { (*dnode.raw).flags |= ffi::LYD_DEFAULT };
+        }
+
+        Ok(())
+    }
+
+    /// Move the data node identified by the given path (along with its
+    /// subtree) under the data node identified by the new parent path,
+    /// returning the moved node.
+    ///
+    /// The new parent must be the schema parent of the node (ignoring choices
+    /// and cases), otherwise an error is returned and the data tree isn't
+    /// modified. The first top-level node of a data tree can't be moved.
+    pub fn move_to(
+        &mut self,
+        path: &str,
+        new_parent: &str,
+    ) -> Result<DataNodeRef<'_>> {
+        let dnode = self.find_node(path)?;
+        let parent = self.find_node(new_parent)?;
+        dnode.check_move_to(&parent)?;
+
+        let raw = dnode.raw;
+        let ret = unsafe { ffi::lyd_insert_child(parent.raw, raw) };
+        if ret != ffi::LY_ERR::LY_SUCCESS {
+            return Err(Error::new(self.context()));
+        }
+
+        Ok(unsafe { DataNodeRef::from_raw(self, raw) })
+    }
+
+    /// Move the user-ordered list entry or leaf-list value identified by the
+    /// given path right before the given sibling instance of the same list or
+    /// leaf-list.
+    ///
+    /// An error is returned and the data tree isn't modified if both nodes
+    /// aren't instances of the same user-ordered list or leaf-list with the
+    /// same parent.
+    pub fn move_before(&mut self, path: &str, anchor: &str) -> Result<()> {
+        self.move_sibling(path, anchor, true)
+    }
+
+    /// Move the user-ordered list entry or leaf-list value identified by the
+    /// given path right after the given sibling instance of the same list or
+    /// leaf-list.
+    ///
+    /// See [`DataTree::move_before`] for the requirements on both nodes.
+    pub fn move_after(&mut self, path: &str, anchor: &str) -> Result<()> {
+        self.move_sibling(path, anchor, false)
+    }
+
+    /// Create a new attribute on the data node identified by the given path,
+    /// which must be an opaque node.
+    ///
+    /// The module name is optional and can also be provided as a prefix of the
+    /// attribute name.
+    pub fn new_attr(
+        &mut self,
+        path: &str,
+        module: Option<&str>,
+        name: &str,
+        value: Option<&str>,
+    ) -> Result<()> {
+        let raw = self.find_node(path)?.raw;
+        let name = CString::new(name).unwrap();
+        let module_cstr;
+        let value_cstr;
+
+        let module_ptr = match module {
+            Some(module) => {
+                module_cstr = CString::new(module).unwrap();
+                module_cstr.as_ptr()
+            }
+            None => std::ptr::null(),
+        };
+        let value_ptr = match value {
+            Some(value) => {
+                value_cstr = CString::new(value).unwrap();
+                value_cstr.as_ptr()
+            }
+            None => std::ptr::null(),
+        };
+
         let ret = unsafe {
-            ffi::lyd_new_path2(
-                self.raw(),
-                self.context().raw,
-                path.as_ptr(),
-                value_ptr as *const c_void,
-                value_len,
-                ffi::LYD_ANYDATA_VALUETYPE::LYD_ANYDATA_STRING,
-                options,
-                rnode_root_ptr,
-                rnode_ptr,
+            ffi::lyd_new_attr(
+                raw,
+                module_ptr,
+                name.as_ptr(),
+                value_ptr,
+                std::ptr::null_mut(),
             )
         };
         if ret != ffi::LY_ERR::LY_SUCCESS {
             return Err(Error::new(self.context()));
         }
 
-        // Update top-level sibling.
+        Ok(())
+    }
+
+    /// Prune the data tree so that only the subtrees selected by the given
+    /// XPath expression remain, along with their ancestors (and the keys of
+    /// ancestor list entries). All other nodes are freed.
+    pub fn trim_to_xpath(&mut self, xpath: &str) -> Result<()> {
         if self.raw.is_null() {
-            self.raw = unsafe { ffi::lyd_first_sibling(rnode_root) };
-        } else {
-            self.raw = unsafe { ffi::lyd_first_sibling(self.raw) };
+            return Ok(());
         }
 
-        Ok(unsafe { DataNodeRef::from_raw_opt(self.tree(), rnode) })
-    }
+        let mut keep = HashSet::new();
+        for dnode in self.find_xpath(xpath)? {
+            keep.extend(dnode.traverse().map(|dnode| dnode.raw));
+            for ancestor in dnode.ancestors() {
+                keep.insert(ancestor.raw);
+                keep.extend(
+                    ancestor
+                        .children()
+                        .filter(|child| {
+                            !unsafe { (*child.raw).schema }.is_null()
+                                && child.schema().is_list_key()
+                        })
+                        .map(|child| child.raw),
+                );
+            }
+        }
+
+        // Free the topmost nodes that aren't kept, along with their subtrees.
+        let remove = self
+            .traverse()
+            .filter(|dnode| {
+                !keep.contains(&dnode.raw)
+                    && match dnode.parent() {
+                        Some(parent) => keep.contains(&parent.raw),
+                        None => true,
+                    }
+            })
+            .map(|dnode| dnode.raw)
+            .collect::<Vec<_>>();
+        for raw in remove {
+            self.free_subtree(raw);
+        }
 
-    /// Remove a data node.
-    pub fn remove(&mut self, path: &str) -> Result<()> {
-        let dnode = self.find_path(path)?;
-        unsafe { ffi::lyd_free_tree(dnode.raw) };
         Ok(())
     }
 
     /// Fully validate the data tree.
+    ///
+    /// libyang doesn't support partial validation: all constraints are
+    /// checked, including the resolution of leafref and union values.
     pub fn validate(&mut self, options: DataValidationFlags) -> Result<()> {
         let ret = unsafe {
             ffi::lyd_validate_all(
@@ -528,6 +1795,88 @@ impl DataTree {
         Ok(())
     }
 
+    /// Fully validate the data tree, resolving its references (e.g. leafrefs
+    /// and instance-identifiers) against the given dependency data as well.
+    ///
+    /// The validation is performed on a copy of the dependency data merged
+    /// with this data tree, whose nodes take precedence. Errors are reported
+    /// for the whole merged data, so errors in the dependency data itself
+    /// fail the validation too. The dependency data isn't modified, and it
+    /// isn't merged into this data tree.
+    ///
+    /// Unlike [`DataTree::validate`], this data tree is only modified on
+    /// success by adding its missing implicit nodes (per the `NO_STATE` and
+    /// `NO_DEFAULTS` options). Other changes made by the validation of the
+    /// merged copy aren't applied to it: nodes whose "when" condition is
+    /// false aren't removed, and duplicate default nodes aren't resolved.
+    pub fn validate_with_dependencies(
+        &mut self,
+        deps: &DataTree,
+        options: DataValidationFlags,
+    ) -> Result<()> {
+        let mut dtree = deps.duplicate()?;
+        dtree.merge(self)?;
+        dtree.validate(options)?;
+
+        let mut implicit_options = DataImplicitFlags::empty();
+        if options.contains(DataValidationFlags::NO_STATE) {
+            implicit_options |= DataImplicitFlags::NO_STATE;
+        }
+        if options.contains(DataValidationFlags::NO_DEFAULTS) {
+            implicit_options |= DataImplicitFlags::NO_DEFAULTS;
+        }
+        self.add_implicit(implicit_options)
+    }
+
+    /// Check whether the data tree is a complete and valid configuration
+    /// datastore.
+    ///
+    /// The data tree is fully validated against all implemented modules, so
+    /// the mandatory nodes of modules without any data in the tree are
+    /// required too, and state data isn't allowed. The validation is performed
+    /// on a copy of the data tree, which is left untouched (e.g. no default
+    /// nodes are added to it).
+    pub fn is_complete(&self) -> bool {
+        self.duplicate()
+            .and_then(|mut dtree| dtree.validate(DataValidationFlags::NO_STATE))
+            .is_ok()
+    }
+
+    /// Fully validate the data tree, returning the paths of the default nodes
+    /// that were implicitly created during the validation.
+    pub fn validate_report_defaults(
+        &mut self,
+        options: DataValidationFlags,
+    ) -> Result<Vec<String>> {
+        let mut rdiff = std::ptr::null_mut();
+        let rdiff_ptr = &mut rdiff;
+
+        let ret = unsafe {
+            ffi::lyd_validate_all(
+                &mut self.raw,
+                self.context.raw,
+                options.bits(),
+                rdiff_ptr,
+            )
+        };
+        let diff = DataDiff {
+            tree: unsafe { DataTree::from_raw(&self.context, rdiff) },
+        };
+        if ret != ffi::LY_ERR::LY_SUCCESS {
+            return Err(Error::new(&self.context));
+        }
+
+        // The validation diff also contains the nodes that were automatically
+        // deleted. The descendants of created nodes don't have their own
+        // operation, hence they need to be traversed explicitly.
+        Ok(diff
+            .iter()
+            .filter(|(op, _, _)| *op == DataDiffOp::Create)
+            .flat_map(|(_, dnode, _)| dnode.traverse())
+            .map(|dnode| dnode.path())
+            .collect())
+    }
+
     /// Create a copy of the data tree.
     pub fn duplicate(&self) -> Result<DataTree> {
         let mut dup = std::ptr::null_mut();
@@ -562,92 +1911,508 @@ impl DataTree {
     pub fn merge(&mut self, source: &DataTree) -> Result<()> {
         // Special handling for empty data trees.
         if self.raw.is_null() {
-            *self = source.duplicate()?;
+            let mut dup = source.duplicate()?;
+            self.raw = std::mem::replace(&mut dup.raw, std::ptr::null_mut());
         } else {
             let options = 0u16;
             let ret = unsafe {
-                ffi::lyd_merge_siblings(&mut self.raw, source.raw, options)
+                ffi::lyd_merge_siblings(&mut self.raw, source.raw, options)
+            };
+            if ret != ffi::LY_ERR::LY_SUCCESS {
+                return Err(Error::new(&self.context));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Merge the source data tree into the target data tree and validate the
+    /// result.
+    ///
+    /// The merge is first performed on a copy of the data tree, so that the
+    /// data tree is left untouched if either the merge or the validation
+    /// fails. Only then the data tree is merged and validated in place, which
+    /// preserves its data nodes (along with their user data) and keeps
+    /// non-owning data trees pointing to the data of their owner.
+    pub fn merge_validate(
+        &mut self,
+        source: &DataTree,
+        options: DataValidationFlags,
+    ) -> Result<()> {
+        let mut dtree = self.duplicate()?;
+        dtree.merge(source)?;
+        dtree.validate(options)?;
+
+        self.merge(source)?;
+        self.validate(options)
+    }
+
+    /// Parse input data and merge it into the data tree.
+    ///
+    /// The input data is parsed without validation, since it may not be
+    /// valid on its own. Unless [`DataParserFlags::NO_VALIDATION`] is given,
+    /// the merged data tree is then validated using the provided validation
+    /// options, as in [`DataTree::merge_validate`]. In case of errors the data
+    /// tree is left untouched.
+    pub fn parse_into(
+        &mut self,
+        data: &str,
+        format: DataFormat,
+        parser_options: DataParserFlags,
+        validation_options: DataValidationFlags,
+    ) -> Result<()> {
+        let source = DataTree::parse_string(
+            &self.context,
+            data,
+            format,
+            parser_options | DataParserFlags::NO_VALIDATION,
+            DataValidationFlags::empty(),
+        )?;
+        if parser_options.contains(DataParserFlags::NO_VALIDATION) {
+            self.merge(&source)
+        } else {
+            self.merge_validate(&source, validation_options)
+        }
+    }
+
+    /// Merge the source data tree into the target data tree, validate the
+    /// result and return the differences between the original and the merged
+    /// data trees.
+    ///
+    /// As in [`DataTree::merge_validate`], the merge is first performed on a
+    /// copy of the data tree, and only then in place if both the merge and
+    /// the validation succeed.
+    pub fn merge_diff(
+        &mut self,
+        source: &DataTree,
+        options: DataValidationFlags,
+    ) -> Result<DataDiff> {
+        let mut dtree = self.duplicate()?;
+        dtree.merge(source)?;
+        dtree.validate(options)?;
+
+        let diff = self.diff(&dtree, DataDiffFlags::empty())?;
+        self.merge(source)?;
+        self.validate(options)?;
+
+        Ok(diff)
+    }
+
+    /// Add any missing implicit nodes. Default nodes with a false "when" are
+    /// not added.
+    pub fn add_implicit(&mut self, options: DataImplicitFlags) -> Result<()> {
+        let ret = unsafe {
+            ffi::lyd_new_implicit_all(
+                &mut self.raw,
+                self.context.raw,
+                options.bits(),
+                std::ptr::null_mut(),
+            )
+        };
+        if ret != ffi::LY_ERR::LY_SUCCESS {
+            return Err(Error::new(&self.context));
+        }
+
+        // Update top-level sibling.
+        self.raw = unsafe { ffi::lyd_first_sibling(self.raw) };
+
+        Ok(())
+    }
+
+    /// Learn the differences between 2 data trees.
+    ///
+    /// The resulting diff is represented as a data tree with specific metadata
+    /// from the internal 'yang' module. Most importantly, every node has an
+    /// effective 'operation' metadata. If there is none defined on the
+    /// node, it inherits the operation from the nearest parent. Top-level nodes
+    /// must always have the 'operation' metadata defined. Additional
+    /// metadata ('orig-default', 'value', 'orig-value', 'key', 'orig-key')
+    /// are used for storing more information about the value in the first
+    /// or the second tree.
+    ///
+    /// Anydata and anyxml nodes are compared by content, including all the
+    /// nodes of embedded data trees. Any difference in their content makes the
+    /// whole node replaced in the diff, with its original content serialized
+    /// in the 'orig-value' metadata.
+    pub fn diff(
+        &self,
+        dtree: &DataTree,
+        options: DataDiffFlags,
+    ) -> Result<DataDiff> {
+        let mut rnode = std::ptr::null_mut();
+        let rnode_ptr = &mut rnode;
+
+        let ret = unsafe {
+            ffi::lyd_diff_siblings(
+                self.raw,
+                dtree.raw,
+                options.bits(),
+                rnode_ptr,
+            )
+        };
+        if ret != ffi::LY_ERR::LY_SUCCESS {
+            return Err(Error::new(&self.context));
+        }
+
+        Ok(DataDiff {
+            tree: unsafe { DataTree::from_raw(&dtree.context, rnode) },
+        })
+    }
+
+    /// Apply the whole diff tree on the data tree.
+    pub fn diff_apply(&mut self, diff: &DataDiff) -> Result<()> {
+        let ret =
+            unsafe { ffi::lyd_diff_apply_all(&mut self.raw, diff.tree.raw) };
+        if ret != ffi::LY_ERR::LY_SUCCESS {
+            return Err(Error::new(&self.context));
+        }
+
+        Ok(())
+    }
+
+    /// Check whether the whole diff tree can be applied on the data tree,
+    /// without modifying it.
+    ///
+    /// The diff is applied on a copy of the data tree, so the returned error
+    /// is the same one [`DataTree::diff_apply`] would return.
+    pub fn diff_apply_check(&self, diff: &DataDiff) -> Result<()> {
+        let mut dup = self.duplicate()?;
+        dup.diff_apply(diff)
+    }
+
+    /// Returns an iterator over all elements in the data tree and its sibling
+    /// trees (depth-first search algorithm).
+    pub fn traverse(&self) -> impl Iterator<Item = DataNodeRef<'_>> {
+        let top = Siblings::new(self.reference());
+        top.flat_map(|dnode| dnode.traverse())
+    }
+
+    /// Returns the number of data nodes in the data tree and its sibling
+    /// trees.
+    pub fn node_count(&self) -> usize {
+        self.traverse().count()
+    }
+
+    /// Check whether the data tree or any of its sibling trees contains state
+    /// data (i.e. non-configuration nodes). Opaque nodes are ignored.
+    pub fn has_state_data(&self) -> bool {
+        self.traverse().any(|dnode| {
+            !unsafe { (*dnode.raw).schema }.is_null()
+                && !dnode.schema().is_config()
+        })
+    }
+
+    /// Returns a fingerprint of the content of the data tree and its sibling
+    /// trees, meant to cheaply detect changes (e.g. between polls of a
+    /// datastore).
+    ///
+    /// The fingerprint is a 64-bit FNV-1a hash of the compact JSON output of
+    /// the data, including default nodes, so it's stable across processes and
+    /// versions of yang2-rs. Note that the order of the nodes is significant:
+    /// libyang doesn't sort the instances of lists and leaf-lists, so the same
+    /// content inserted in a different order may yield different fingerprints.
+    /// Equal fingerprints are only meaningful for data trees whose nodes were
+    /// inserted in the same (canonical) order.
+    pub fn fingerprint(&self) -> Result<u64> {
+        let mut hash = FNV_OFFSET_BASIS;
+        let options = DataPrinterFlags::WITH_SIBLINGS
+            | DataPrinterFlags::SHRINK
+            | DataPrinterFlags::WD_ALL;
+        self.print_chunks(DataFormat::JSON, options, |chunk| {
+            for byte in chunk {
+                hash = (hash ^ *byte as u64).wrapping_mul(FNV_PRIME);
+            }
+            Ok(())
+        })?;
+        Ok(hash)
+    }
+
+    /// Returns a rough estimate of the memory used by the data tree, in
+    /// bytes.
+    ///
+    /// The estimate accounts for the data node structures, their canonical
+    /// values and their metadata. Hash tables and strings shared with the
+    /// context dictionary aren't accounted for, so this is only meant to be
+    /// used as a relative weight (e.g. for cache sizing).
+    pub fn estimated_size(&self) -> usize {
+        self.traverse()
+            .map(|dnode| {
+                let schema = unsafe { (*dnode.raw).schema };
+                let node_size = if schema.is_null() {
+                    let ropaq = dnode.raw as *const ffi::lyd_node_opaq;
+                    let value = unsafe { (*ropaq).value };
+                    std::mem::size_of::<ffi::lyd_node_opaq>()
+                        + char_ptr_to_opt_str(value).map_or(0, str::len)
+                } else {
+                    match unsafe { (*schema).nodetype } as u32 {
+                        ffi::LYS_LEAF | ffi::LYS_LEAFLIST => {
+                            std::mem::size_of::<ffi::lyd_node_term>()
+                                + dnode
+                                    .value_canonical()
+                                    .map_or(0, |value| value.len())
+                        }
+                        ffi::LYS_ANYDATA | ffi::LYS_ANYXML => {
+                            std::mem::size_of::<ffi::lyd_node_any>()
+                        }
+                        _ => std::mem::size_of::<ffi::lyd_node_inner>(),
+                    }
+                };
+                let meta_size =
+                    dnode.meta().count() * std::mem::size_of::<ffi::lyd_meta>();
+                node_size + meta_size
+            })
+            .sum()
+    }
+
+    /// Returns the modules that have data in the data tree, i.e. the distinct
+    /// owner modules of its top-level data nodes.
+    pub fn modules_with_data(&self) -> Vec<SchemaModule<'_>> {
+        let mut modules = Vec::new();
+        for dnode in Siblings::new(self.reference()) {
+            let rmodule = unsafe { ffi::lyd_owner_module(dnode.raw) };
+            let module = unsafe {
+                SchemaModule::from_raw_opt(&self.context, rmodule as *mut _)
+            };
+            if let Some(module) = module {
+                if !modules.contains(&module) {
+                    modules.push(module);
+                }
+            }
+        }
+        modules
+    }
+
+    /// Apply a NETCONF subtree filter (RFC 6241, section 6) to the data
+    /// tree, returning a new data tree with the selected data.
+    ///
+    /// The filter is a data tree usually parsed with
+    /// [`DataParserFlags::NO_VALIDATION`] and [`DataParserFlags::OPAQ`], so
+    /// that empty selection leaves and list entries without keys are kept as
+    /// opaque nodes. Leaves with a value are content match nodes, compared
+    /// against the canonical values of the data. Other leaves and empty
+    /// containers are selection nodes, while nodes with children are
+    /// containment nodes. An empty filter selects no data.
+    pub fn apply_subtree_filter(&self, filter: &DataTree) -> Result<DataTree> {
+        let mut dtree = DataTree::new(&self.context);
+        let filters = Siblings::new(filter.reference()).collect::<Vec<_>>();
+        if filters.is_empty() {
+            return Ok(dtree);
+        }
+
+        let nodes =
+            match subtree_filter(&filters, Siblings::new(self.reference())) {
+                Some(FilterMatch::All) => return self.duplicate(),
+                Some(FilterMatch::Nodes(nodes)) => nodes,
+                None => return Ok(dtree),
+            };
+        for dnode in Siblings::new(self.reference()) {
+            let selection = match nodes.iter().find(|(node, _)| *node == dnode)
+            {
+                Some((_, selection)) => selection,
+                None => continue,
+            };
+            let dup =
+                subtree_filter_dup(&dnode, selection, std::ptr::null_mut())?;
+            let ret = unsafe {
+                ffi::lyd_insert_sibling(dtree.raw, dup, &mut dtree.raw)
+            };
+            if ret != ffi::LY_ERR::LY_SUCCESS {
+                unsafe { ffi::lyd_free_tree(dup) };
+                return Err(Error::new(&self.context));
+            }
+        }
+
+        Ok(dtree)
+    }
+
+    /// Duplicate the top-level data nodes of the given module, along with their
+    /// subtrees, into a new data tree.
+    pub fn extract_module(
+        &self,
+        module: &SchemaModule<'_>,
+    ) -> Result<DataTree> {
+        let mut dtree = DataTree::new(&self.context);
+        for dnode in Siblings::new(self.reference()) {
+            if unsafe { (*dnode.raw).schema }.is_null()
+                || dnode.owner_module() != *module
+            {
+                continue;
+            }
+
+            let mut dup = std::ptr::null_mut();
+            let options = ffi::LYD_DUP_RECURSIVE | ffi::LYD_DUP_WITH_FLAGS;
+            let ret = unsafe {
+                ffi::lyd_dup_single(
+                    dnode.raw,
+                    std::ptr::null_mut(),
+                    options,
+                    &mut dup,
+                )
+            };
+            if ret != ffi::LY_ERR::LY_SUCCESS {
+                return Err(Error::new(&self.context));
+            }
+            let ret = unsafe {
+                ffi::lyd_insert_sibling(dtree.raw, dup, &mut dtree.raw)
             };
             if ret != ffi::LY_ERR::LY_SUCCESS {
+                unsafe { ffi::lyd_free_tree(dup) };
                 return Err(Error::new(&self.context));
             }
         }
 
-        Ok(())
+        Ok(dtree)
     }
 
-    /// Add any missing implicit nodes. Default nodes with a false "when" are
-    /// not added.
-    pub fn add_implicit(&mut self, options: DataImplicitFlags) -> Result<()> {
+    /// Check whether the data tree is equivalent to another one, comparing all
+    /// their nodes recursively.
+    pub fn equivalent(
+        &self,
+        other: &DataTree,
+        options: DataCompareFlags,
+    ) -> bool {
+        let options = options.bits() | ffi::LYD_COMPARE_FULL_RECURSION;
+        let ret =
+            unsafe { ffi::lyd_compare_siblings(self.raw, other.raw, options) };
+        ret == ffi::LY_ERR::LY_SUCCESS
+    }
+
+    /// Check whether the data tree is equivalent to another one like
+    /// [`DataTree::equivalent`], but ignoring the relative order of the
+    /// sibling nodes.
+    ///
+    /// Unlike [`DataTree::equivalent`], the comparison is done by yang2-rs
+    /// itself: each node is matched against all of its remaining candidate
+    /// siblings in the other data tree, so the comparison takes quadratic
+    /// time in the number of siblings.
+    pub fn equivalent_unordered(
+        &self,
+        other: &DataTree,
+        options: DataCompareFlags,
+    ) -> bool {
+        compare_siblings_unordered(
+            self.reference(),
+            other.reference(),
+            options.bits(),
+            false,
+        )
+    }
+
+    // Find the data node identified by the given path, as generated by
+    // `DataNodeRef::path`. Opaque nodes can't be found using schema-based
+    // lookups, so they're looked up by comparing their generated paths.
+    fn find_node(&self, path: &str) -> Result<DataNodeRef<'_>> {
+        match self.find_path(path, false) {
+            Ok(dnode) => Ok(dnode),
+            Err(error) => self
+                .traverse()
+                .find(|dnode| {
+                    dnode.node_type() == DataNodeType::Opaque
+                        && dnode.path() == path
+                })
+                .ok_or(error),
+        }
+    }
+
+    // Move a user-ordered list entry or leaf-list value right before or after
+    // the given anchor node.
+    fn move_sibling(
+        &mut self,
+        path: &str,
+        anchor: &str,
+        before: bool,
+    ) -> Result<()> {
+        let dnode = self.find_node(path)?;
+        let anchor = self.find_node(anchor)?;
+        dnode.check_move(&anchor, before)?;
+
         let ret = unsafe {
-            ffi::lyd_new_implicit_all(
-                &mut self.raw,
-                self.context.raw,
-                options.bits(),
-                std::ptr::null_mut(),
-            )
+            if before {
+                ffi::lyd_insert_before(anchor.raw, dnode.raw)
+            } else {
+                ffi::lyd_insert_after(anchor.raw, dnode.raw)
+            }
         };
         if ret != ffi::LY_ERR::LY_SUCCESS {
-            return Err(Error::new(&self.context));
+            return Err(Error::new(self.context()));
         }
 
-        // Update top-level sibling.
-        self.raw = unsafe { ffi::lyd_first_sibling(self.raw) };
-
         Ok(())
     }
 
-    /// Learn the differences between 2 data trees.
-    ///
-    /// The resulting diff is represented as a data tree with specific metadata
-    /// from the internal 'yang' module. Most importantly, every node has an
-    /// effective 'operation' metadata. If there is none defined on the
-    /// node, it inherits the operation from the nearest parent. Top-level nodes
-    /// must always have the 'operation' metadata defined. Additional
-    /// metadata ('orig-default', 'value', 'orig-value', 'key', 'orig-key')
-    /// are used for storing more information about the value in the first
-    /// or the second tree.
-    pub fn diff(
-        &self,
-        dtree: &DataTree,
-        options: DataDiffFlags,
-    ) -> Result<DataDiff> {
+    // Free a data node of the data tree along with its subtree, including the
+    // attached user data.
+    fn free_subtree(&mut self, raw: *mut ffi::lyd_node) {
+        if self.user_data {
+            let dnode = unsafe { DataNodeRef::from_raw(self, raw) };
+            for dnode in dnode.traverse() {
+                unsafe { free_user_data(dnode.raw) };
+            }
+        }
+        if raw == self.raw {
+            // Update top-level sibling.
+            self.raw = unsafe { (*raw).next };
+        }
+        unsafe { ffi::lyd_free_tree(raw) };
+    }
+
+    // Create a new node or modify existing one in the data tree based on a
+    // path, using the given libyang options.
+    fn new_path_options(
+        &mut self,
+        path: &str,
+        value: Option<&str>,
+        options: u32,
+    ) -> Result<NewPathOutcome<'_>> {
+        let path = CString::new(path).unwrap();
+        let mut rnode_root = std::ptr::null_mut();
         let mut rnode = std::ptr::null_mut();
+        let rnode_root_ptr = &mut rnode_root;
         let rnode_ptr = &mut rnode;
+        let value_cstr;
+
+        let (value_ptr, value_len) = match value {
+            Some(value) => {
+                value_cstr = CString::new(value).unwrap();
+                (value_cstr.as_ptr(), value.len())
+            }
+            None => (std::ptr::null(), 0),
+        };
 
         let ret = unsafe {
-            ffi::lyd_diff_siblings(
-                self.raw,
-                dtree.raw,
-                options.bits(),
+            ffi::lyd_new_path2(
+                self.raw(),
+                self.context().raw,
+                path.as_ptr(),
+                value_ptr as *const c_void,
+                value_len,
+                ffi::LYD_ANYDATA_VALUETYPE::LYD_ANYDATA_STRING,
+                options,
+                rnode_root_ptr,
                 rnode_ptr,
             )
         };
         if ret != ffi::LY_ERR::LY_SUCCESS {
-            return Err(Error::new(&self.context));
+            return Err(Error::new(self.context()));
         }
 
-        Ok(DataDiff {
-            tree: unsafe { DataTree::from_raw(&dtree.context, rnode) },
-        })
-    }
-
-    /// Apply the whole diff tree on the data tree.
-    pub fn diff_apply(&mut self, diff: &DataDiff) -> Result<()> {
-        let ret =
-            unsafe { ffi::lyd_diff_apply_all(&mut self.raw, diff.tree.raw) };
-        if ret != ffi::LY_ERR::LY_SUCCESS {
-            return Err(Error::new(&self.context));
+        // Update top-level sibling.
+        if self.raw.is_null() {
+            self.raw = unsafe { ffi::lyd_first_sibling(rnode_root) };
+        } else {
+            self.raw = unsafe { ffi::lyd_first_sibling(self.raw) };
         }
 
-        Ok(())
-    }
-
-    /// Returns an iterator over all elements in the data tree and its sibling
-    /// trees (depth-first search algorithm).
-    pub fn traverse(&self) -> impl Iterator<Item = DataNodeRef<'_>> {
-        let top = Siblings::new(self.reference());
-        top.flat_map(|dnode| dnode.traverse())
+        // libyang returns the first created node (if any) and the last created
+        // or modified node (if any).
+        let dnode = unsafe { DataNodeRef::from_raw_opt(self.tree(), rnode) };
+        Ok(match dnode {
+            Some(dnode) if !rnode_root.is_null() => {
+                NewPathOutcome::Created(dnode)
+            }
+            Some(dnode) => NewPathOutcome::Updated(dnode),
+            None => NewPathOutcome::Unchanged,
+        })
     }
 }
 
@@ -672,6 +2437,8 @@ unsafe impl<'a> Binding<'a> for DataTree {
         DataTree {
             context: context.clone(),
             raw,
+            owned: true,
+            user_data: false,
         }
     }
 }
@@ -681,7 +2448,14 @@ unsafe impl Sync for DataTree {}
 
 impl Drop for DataTree {
     fn drop(&mut self) {
-        unsafe { ffi::lyd_free_all(self.raw) };
+        if self.owned {
+            if self.user_data {
+                for dnode in self.traverse() {
+                    unsafe { free_user_data(dnode.raw) };
+                }
+            }
+            unsafe { ffi::lyd_free_all(self.raw) };
+        }
     }
 }
 
@@ -689,11 +2463,25 @@ impl Drop for DataTree {
 
 impl<'a> DataNodeRef<'a> {
     /// Schema definition of this node.
+    ///
+    /// Must not be called on opaque nodes, which have no schema definition.
+    /// Use [`DataNodeRef::node_type`] to tell them apart.
     pub fn schema(&self) -> SchemaNode<'_> {
         let raw = unsafe { (*self.raw).schema };
         unsafe { SchemaNode::from_raw(self.context(), raw as *mut _) }
     }
 
+    /// Type of this node, which is either the kind of its schema definition or
+    /// [`DataNodeType::Opaque`] for opaque nodes.
+    pub fn node_type(&self) -> DataNodeType {
+        let raw = unsafe { (*self.raw).schema };
+        match unsafe { SchemaNode::from_raw_opt(self.context(), raw as *mut _) }
+        {
+            Some(snode) => DataNodeType::Schema(snode.kind()),
+            None => DataNodeType::Opaque,
+        }
+    }
+
     /// Get the owner module of the data node. It is the module of the top-level
     /// schema node. Generally, in case of augments it is the target module,
     /// recursively, otherwise it is the module where the data node is defined.
@@ -724,23 +2512,164 @@ impl<'a> DataNodeRef<'a> {
         Siblings::new(Some(self.clone()))
     }
 
+    /// Returns an iterator over the sibling data nodes that are instances of
+    /// the same schema node as this node (e.g. the following entries of a
+    /// list). Nothing is yielded for opaque nodes.
+    pub fn same_schema_siblings(
+        &self,
+    ) -> impl Iterator<Item = DataNodeRef<'a>> {
+        let rschema = unsafe { (*self.raw).schema };
+        self.siblings().filter(move |dnode| {
+            !rschema.is_null() && unsafe { (*dnode.raw).schema } == rschema
+        })
+    }
+
     /// Returns an iterator over the child data nodes.
     pub fn children(&self) -> Siblings<'a, DataNodeRef<'a>> {
         let child = self.first_child();
         Siblings::new(child)
     }
 
+    /// Returns an iterator over the lists among the child data nodes. Each
+    /// list is yielded as its schema node along with all of its entries, in
+    /// the order they appear.
+    pub fn lists(
+        &self,
+    ) -> impl Iterator<Item = (SchemaNode<'a>, Vec<DataNodeRef<'a>>)> {
+        let context = &*self.tree.context;
+        let mut lists: Vec<(SchemaNode<'a>, Vec<DataNodeRef<'a>>)> = vec![];
+        for dnode in self.children() {
+            let rschema = unsafe { (*dnode.raw).schema };
+            if rschema.is_null()
+                || unsafe { (*rschema).nodetype } as u32 != ffi::LYS_LIST
+            {
+                continue;
+            }
+            let snode =
+                unsafe { SchemaNode::from_raw(context, rschema as *mut _) };
+
+            // Instances of the same schema node are always adjacent.
+            match lists.last_mut() {
+                Some((last, entries)) if *last == snode => entries.push(dnode),
+                _ => lists.push((snode, vec![dnode])),
+            }
+        }
+        lists.into_iter()
+    }
+
+    /// Returns an iterator over the child data nodes that are instances of
+    /// the given schema node (e.g. the entries of a list).
+    pub fn list_entries(
+        &self,
+        snode: &SchemaNode<'a>,
+    ) -> impl Iterator<Item = DataNodeRef<'a>> {
+        let context = &*self.tree.context;
+        let snode = snode.clone();
+        self.children().filter(move |dnode| {
+            let rschema = unsafe { (*dnode.raw).schema };
+            unsafe { SchemaNode::from_raw_opt(context, rschema as *mut _) }
+                .filter(|dsnode| *dsnode == snode)
+                .is_some()
+        })
+    }
+
     /// Returns an iterator over all elements in the data tree (depth-first
     /// search algorithm).
     pub fn traverse(&self) -> Traverse<'a, DataNodeRef<'a>> {
         Traverse::new(self.clone())
     }
 
+    /// Print only this node and its descendants in the specified format,
+    /// ignoring the [`DataPrinterFlags::WITH_SIBLINGS`] option.
+    pub fn print_subtree_file<F: AsRawFd>(
+        &self,
+        fd: F,
+        format: DataFormat,
+        options: DataPrinterFlags,
+    ) -> Result<()> {
+        self.print_file(fd, format, options - DataPrinterFlags::WITH_SIBLINGS)
+    }
+
+    /// Returns an iterator over all configuration elements in the data tree
+    /// (depth-first search algorithm).
+    ///
+    /// Subtrees rooted at state nodes are pruned, so their descendants aren't
+    /// visited at all. Opaque nodes are always yielded.
+    pub fn traverse_config(&self) -> impl Iterator<Item = DataNodeRef<'a>> {
+        let mut stack = vec![self.clone()];
+        std::iter::from_fn(move || {
+            while let Some(dnode) = stack.pop() {
+                let schema = unsafe { (*dnode.raw).schema };
+                if !schema.is_null() && !dnode.schema().is_config() {
+                    continue;
+                }
+                let mut children = dnode.children().collect::<Vec<_>>();
+                children.reverse();
+                stack.extend(children);
+                return Some(dnode);
+            }
+            None
+        })
+    }
+
+    /// Returns an iterator over all elements in the data tree up to the given
+    /// depth (depth-first search algorithm). Each element is yielded along
+    /// with its depth relative to this node, which has depth zero.
+    pub fn traverse_depth(
+        &self,
+        max_depth: usize,
+    ) -> TraverseDepth<'a, DataNodeRef<'a>> {
+        TraverseDepth::new(self.clone(), max_depth)
+    }
+
+    /// Walks over all elements in the data tree (depth-first search
+    /// algorithm), calling the visitor when each element is entered and left.
+    pub fn walk<V: DataVisitor<'a>>(&self, visitor: &mut V) {
+        self.walk_depth(visitor, 0);
+    }
+
+    /// Returns an iterator over all elements in the data tree (depth-first
+    /// search algorithm), each one along with its parent and whether it's the
+    /// last of its siblings.
+    ///
+    /// The parent of this node is provided as well, even though it isn't
+    /// part of the walk.
+    pub fn walk_with_context(&self) -> impl Iterator<Item = WalkEvent<'a>> {
+        self.traverse().map(|node| WalkEvent {
+            parent: node.parent(),
+            is_last: node.next_sibling().is_none(),
+            node,
+        })
+    }
+
+    /// Folds all elements in the data tree into an accumulator (depth-first
+    /// search algorithm). The closure is called with the current accumulator,
+    /// the element and its depth relative to this node, which has depth zero.
+    pub fn fold<T, F>(&self, init: T, mut f: F) -> T
+    where
+        F: FnMut(T, &DataNodeRef<'a>, usize) -> T,
+    {
+        self.traverse_depth(usize::MAX)
+            .fold(init, |acc, (dnode, depth)| f(acc, &dnode, depth))
+    }
+
     /// Returns an iterator over the keys of the list.
     pub fn list_keys(&self) -> impl Iterator<Item = DataNodeRef<'a>> {
         self.children().filter(|dnode| dnode.schema().is_list_key())
     }
 
+    /// Returns the names and values (canonical string representation) of the
+    /// keys of the list, in the order they're defined in the schema.
+    pub fn key_values(&self) -> Vec<(String, String)> {
+        self.list_keys()
+            .map(|dnode| {
+                let name = dnode.schema().name().to_owned();
+                let value = dnode.value_canonical().unwrap_or_default();
+                (name, value)
+            })
+            .collect()
+    }
+
     /// Returns an iterator over all metadata associated to this node.
     pub fn meta(&self) -> MetadataList<'_> {
         let rmeta = unsafe { (*self.raw).meta };
@@ -748,7 +2677,65 @@ impl<'a> DataNodeRef<'a> {
         MetadataList::new(meta)
     }
 
+    /// Returns the metadata with the given name associated to this node, if
+    /// any.
+    pub fn get_meta(&self, name: &str) -> Option<Metadata<'_>> {
+        self.find_meta(None, name)
+    }
+
+    /// Returns the metadata with the given module and name associated to this
+    /// node, if any. When no module is given, the first metadata with the
+    /// given name is returned, regardless of its module.
+    pub fn find_meta(
+        &self,
+        module: Option<&str>,
+        name: &str,
+    ) -> Option<Metadata<'_>> {
+        self.meta().find(|meta| {
+            meta.name() == name
+                && (module.is_none() || module == Some(meta.module().name()))
+        })
+    }
+
+    /// Returns the NETCONF edit-config operation of this node, taken from its
+    /// "ietf-netconf:operation" metadata, if any.
+    ///
+    /// Only the operation set on the node itself is returned. As per RFC 6241,
+    /// nodes without an operation inherit the one of their closest ancestor
+    /// (or the default operation of the request).
+    pub fn edit_operation(&self) -> Option<EditOperation> {
+        let meta = self.find_meta(Some("ietf-netconf"), "operation")?;
+        let op = match meta.value() {
+            "merge" => EditOperation::Merge,
+            "replace" => EditOperation::Replace,
+            "create" => EditOperation::Create,
+            "delete" => EditOperation::Delete,
+            "remove" => EditOperation::Remove,
+            "none" => EditOperation::None,
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    /// Returns an iterator over all attributes associated to this node. Only
+    /// opaque nodes can have attributes.
+    pub fn attrs(&self) -> Attributes<'_> {
+        let rattr = if unsafe { (*self.raw).schema }.is_null() {
+            let ropaq = self.raw as *mut ffi::lyd_node_opaq;
+            unsafe { (*ropaq).attr }
+        } else {
+            std::ptr::null_mut()
+        };
+        let attr = unsafe { Attribute::from_raw_opt(self, rattr) };
+        Attributes::new(attr)
+    }
+
     /// Generate path of the given node.
+    ///
+    /// The path uniquely identifies the node (including list keys and
+    /// leaf-list values) and remains valid for as long as the node exists in
+    /// the data tree, so it can be used to locate the node again using
+    /// [`DataTree::relocate`].
     pub fn path(&self) -> String {
         let mut buf: [c_char; 4096] = [0; 4096];
 
@@ -783,6 +2770,74 @@ impl<'a> DataNodeRef<'a> {
         }
     }
 
+    /// Value of a leaf or leaf-list node in the given format.
+    ///
+    /// libyang doesn't expose its format-aware printing of values, so the
+    /// prefixes of identityref and instance-identifier values (unions
+    /// included) are converted from the canonical value. Returns `None` if
+    /// this isn't a leaf or leaf-list node, or if the value references a
+    /// module that can't be prefixed in the [`ValueFormat::SchemaName`]
+    /// format (i.e. not imported by the module of this node).
+    pub fn value_formatted(&self, format: ValueFormat) -> Option<String> {
+        if !self.is_term() {
+            return None;
+        }
+        let canonical = self.value_canonical()?;
+        if matches!(format, ValueFormat::Canonical | ValueFormat::Json) {
+            return Some(canonical);
+        }
+
+        let context = self.context();
+        let rmodule = unsafe { (*(*self.raw).schema).module };
+        let prefix = |name: &str| -> Option<&str> {
+            if format == ValueFormat::Xml {
+                let module = context.get_module_implemented(name)?;
+                return Some(module.prefix());
+            }
+            import_prefix(rmodule, name)
+        };
+
+        let rvalue = self.resolved_value()?;
+        match unsafe { (*(*rvalue).realtype).basetype } {
+            ffi::LY_DATA_TYPE::LY_TYPE_IDENT => {
+                let (module, name) = canonical.split_once(':')?;
+                Some(format!("{}:{}", prefix(module)?, name))
+            }
+            ffi::LY_DATA_TYPE::LY_TYPE_INST => {
+                instance_id_with_prefixes(&canonical, prefix)
+            }
+            _ => Some(canonical),
+        }
+    }
+
+    /// Check whether the value of this leaf(-list) is equal to the value of
+    /// another one, which may belong to a different data tree.
+    ///
+    /// The comparison is type-aware: the value of the other node is
+    /// interpreted according to the type of this node (e.g. "1.0" and "1.00"
+    /// are equal decimal64 values). Returns false if any of the nodes isn't a
+    /// leaf or leaf-list.
+    pub fn value_equal(&self, other: &DataNodeRef<'_>) -> bool {
+        if !self.is_term() || !other.is_term() {
+            return false;
+        }
+
+        let value = match other.value_canonical() {
+            Some(value) => value,
+            None => return false,
+        };
+        let value_len = value.len();
+        let value = CString::new(value).unwrap();
+        let ret = unsafe {
+            ffi::lyd_value_compare(
+                self.raw as *const ffi::lyd_node_term,
+                value.as_ptr(),
+                value_len,
+            )
+        };
+        ret == ffi::LY_ERR::LY_SUCCESS
+    }
+
     /// Node's value (typed representation).
     pub fn value(&self) -> Option<DataValue> {
         match self.schema().kind() {
@@ -798,6 +2853,155 @@ impl<'a> DataNodeRef<'a> {
         }
     }
 
+    /// Identity the value of a leaf or leaf-list node of an `identityref`-based
+    /// type resolved to.
+    ///
+    /// Unlike the string value, the identity doesn't depend on the prefix
+    /// used to reference it, so it's suitable for comparisons. Unions are
+    /// descended into. Returns `None` if the value isn't an identityref.
+    pub fn identityref_value(&self) -> Option<SchemaIdentity<'_>> {
+        if !self.is_term() {
+            return None;
+        }
+
+        let rvalue = self.resolved_value()?;
+        if unsafe { (*(*rvalue).realtype).basetype }
+            != ffi::LY_DATA_TYPE::LY_TYPE_IDENT
+        {
+            return None;
+        }
+        let rident = unsafe { (*rvalue).__bindgen_anon_1.ident };
+        unsafe { SchemaIdentity::from_raw_opt(self.context(), rident) }
+    }
+
+    /// Decoded value of a leaf or leaf-list node of a `binary`-based type.
+    ///
+    /// The bytes are taken from libyang's internal representation of the
+    /// value, so no base64 decoding takes place. Returns `None` if this node
+    /// doesn't hold a binary value. Leafrefs and unions resolving to a binary
+    /// value are supported.
+    pub fn value_bytes(&self) -> Option<Vec<u8>> {
+        let rvalue = self.resolved_value()?;
+        if unsafe { (*(*rvalue).realtype).basetype }
+            != ffi::LY_DATA_TYPE::LY_TYPE_BINARY
+        {
+            return None;
+        }
+
+        // Binary values are small enough to be stored in the fixed memory of
+        // the value (see the LYD_VALUE_GET macro).
+        let rbinary = unsafe {
+            (*rvalue).__bindgen_anon_1.fixed_mem.as_ptr()
+                as *const ffi::lyd_value_binary
+        };
+        let (data, size) = unsafe { ((*rbinary).data, (*rbinary).size) };
+        if data.is_null() || size == 0 {
+            return Some(Vec::new());
+        }
+        let bytes = unsafe { slice::from_raw_parts(data as *const u8, size) };
+        Some(bytes.to_vec())
+    }
+
+    /// Value of an anydata or anyxml node, in its internal representation.
+    ///
+    /// Returns `None` if this isn't an anydata/anyxml node or if it has no
+    /// value.
+    pub fn any_value(&self) -> Option<DataAnyValue<'a>> {
+        if !self.is_any() {
+            return None;
+        }
+
+        let rnode = self.raw as *const ffi::lyd_node_any;
+        let value = unsafe { (*rnode).value };
+        let value_type = unsafe { (*rnode).value_type };
+        unsafe {
+            match value_type {
+                ffi::LYD_ANYDATA_VALUETYPE::LYD_ANYDATA_DATATREE => {
+                    DataNodeRef::from_raw_opt(self.tree, value.tree)
+                        .map(DataAnyValue::DataTree)
+                }
+                ffi::LYD_ANYDATA_VALUETYPE::LYD_ANYDATA_STRING => {
+                    char_ptr_to_opt_str(value.str_).map(DataAnyValue::String)
+                }
+                ffi::LYD_ANYDATA_VALUETYPE::LYD_ANYDATA_XML => {
+                    char_ptr_to_opt_str(value.xml).map(DataAnyValue::Xml)
+                }
+                ffi::LYD_ANYDATA_VALUETYPE::LYD_ANYDATA_JSON => {
+                    char_ptr_to_opt_str(value.json).map(DataAnyValue::Json)
+                }
+                ffi::LYD_ANYDATA_VALUETYPE::LYD_ANYDATA_LYB => {
+                    if value.mem.is_null() {
+                        return None;
+                    }
+                    let len = ffi::lyd_lyb_data_length(value.mem);
+                    if len < 0 {
+                        return None;
+                    }
+                    let bytes =
+                        slice::from_raw_parts(value.mem as *const u8, len as _);
+                    Some(DataAnyValue::Lyb(bytes))
+                }
+                _ => None,
+            }
+        }
+    }
+
+    /// Value of an anydata or anyxml node serialized as a string, regardless
+    /// of its internal representation.
+    ///
+    /// Returns `None` if this isn't an anydata/anyxml node or if it has no
+    /// value.
+    pub fn any_value_string(&self) -> Result<Option<String>> {
+        if !self.is_any() {
+            return Ok(None);
+        }
+
+        let mut cstr = std::ptr::null_mut();
+        let cstr_ptr = &mut cstr;
+
+        let ret = unsafe { ffi::lyd_any_value_str(self.raw, cstr_ptr) };
+        if ret != ffi::LY_ERR::LY_SUCCESS {
+            return Err(Error::new(self.context()));
+        }
+
+        let value = char_ptr_to_opt_string(cstr);
+        unsafe { ffi::free(cstr as *mut c_void) };
+        Ok(value)
+    }
+
+    /// Node's decimal64 value as a scaled integer, along with the number of
+    /// fraction digits of its type. The actual value is
+    /// `value / 10^fraction_digits`.
+    ///
+    /// Returns `None` if the node doesn't hold a decimal64 value. Leafrefs and
+    /// unions resolving to a decimal64 value are supported.
+    pub fn decimal64(&self) -> Option<(i64, u8)> {
+        let rvalue = self.resolved_value()?;
+        let rtype = unsafe { (*rvalue).realtype };
+        if unsafe { (*rtype).basetype } != ffi::LY_DATA_TYPE::LY_TYPE_DEC64 {
+            return None;
+        }
+
+        let value = unsafe { (*rvalue).__bindgen_anon_1.dec64 };
+        let fraction_digits =
+            unsafe { (*(rtype as *const ffi::lysc_type_dec)).fraction_digits };
+        Some((value, fraction_digits))
+    }
+
+    /// Type the node's value was resolved to. For leafrefs, this is the type
+    /// of the target leaf. For unions, this is the member type that matched
+    /// the value.
+    ///
+    /// Since the resolved type isn't associated to any schema node, its
+    /// [`SchemaLeafType::type_name`] is never available.
+    pub fn resolved_type(&self) -> Option<SchemaLeafType<'_>> {
+        let rvalue = self.resolved_value()?;
+        let rtype = unsafe { (*rvalue).realtype };
+        Some(unsafe {
+            SchemaLeafType::from_raw(self.context(), rtype as *mut _)
+        })
+    }
+
     /// Check whether a node value equals to its default one.
     pub fn is_default(&self) -> bool {
         match self.schema().kind() {
@@ -808,6 +3012,54 @@ impl<'a> DataNodeRef<'a> {
         }
     }
 
+    /// Describe the node, bundling its path, type, value and default status.
+    ///
+    /// The [`NodeDescription`] can be printed using its `Display`
+    /// implementation, e.g. `/example:config/enabled (Leaf, Bool) = true`.
+    pub fn describe(&self) -> NodeDescription {
+        let node_type = self.node_type();
+        let ltype = match node_type {
+            DataNodeType::Schema(_) => self.schema().leaf_type(),
+            DataNodeType::Opaque => None,
+        };
+
+        NodeDescription {
+            path: self.path(),
+            node_type,
+            value_type: ltype.as_ref().map(|ltype| ltype.base_type()),
+            type_name: ltype
+                .as_ref()
+                .and_then(|ltype| ltype.type_name())
+                .map(str::to_owned),
+            value: self.value_canonical(),
+            is_default: node_type != DataNodeType::Opaque && self.is_default(),
+        }
+    }
+
+    /// Check whether the "when" conditions of this node were satisfied the last
+    /// time the data tree was validated.
+    ///
+    /// Returns `None` if the node isn't subject to any "when" condition (see
+    /// [`SchemaNode::whens`]), if it's an opaque node, or if it was created
+    /// after the last validation, in which case its conditions weren't
+    /// evaluated yet. Note that nodes whose conditions evaluate to false are
+    /// automatically deleted during validation.
+    pub fn when_satisfied(&self) -> Option<bool> {
+        if unsafe { (*self.raw).schema }.is_null() {
+            return None;
+        }
+        let snode = self.schema();
+        if snode.whens().count() == 0 {
+            return None;
+        }
+
+        let flags = unsafe { (*self.raw).flags };
+        if flags & ffi::LYD_NEW != 0 {
+            return None;
+        }
+        Some(flags & ffi::LYD_WHEN_TRUE != 0)
+    }
+
     /// Create a copy of the data subtree.
     ///
     /// When the `with_parents` parameter is set, duplicate also all the node
@@ -842,11 +3094,27 @@ impl<'a> DataNodeRef<'a> {
         Ok(unsafe { DataTree::from_raw(self.context(), dup) })
     }
 
+    /// Get the user data attached to this node, if any and if it's of the
+    /// requested type. See [`DataTree::set_user_data`].
+    pub fn user_data<T: Any + Send + Sync>(&self) -> Option<&'a T> {
+        // The private pointer isn't ours unless typed user data is used.
+        if !self.tree.user_data {
+            return None;
+        }
+        let priv_ = unsafe { (*self.raw).priv_ } as *const UserData;
+        if priv_.is_null() {
+            return None;
+        }
+        unsafe { &*priv_ }.downcast_ref::<T>()
+    }
+
     /// Set private user data, not used by libyang.
     ///
     /// # Safety
     ///
-    /// The caller must ensure that the provided pointer is valid.
+    /// The caller must ensure that the provided pointer is valid, and that
+    /// typed user data (see [`DataTree::set_user_data`]) isn't used in the
+    /// data tree of the node.
     pub unsafe fn set_private(&mut self, ptr: *mut c_void) {
         (*self.raw).priv_ = ptr;
     }
@@ -860,6 +3128,128 @@ impl<'a> DataNodeRef<'a> {
             Some(priv_)
         }
     }
+
+    // Returns the value of the leaf(-list), descending into the actual value
+    // of unions.
+    fn resolved_value(&self) -> Option<*const ffi::lyd_value> {
+        if !self.is_term() {
+            return None;
+        }
+
+        let rnode = self.raw as *const ffi::lyd_node_term;
+        let mut rvalue: *const ffi::lyd_value = unsafe { &(*rnode).value };
+        while unsafe { (*(*rvalue).realtype).basetype }
+            == ffi::LY_DATA_TYPE::LY_TYPE_UNION
+        {
+            let subvalue = unsafe { (*rvalue).__bindgen_anon_1.subvalue };
+            rvalue = unsafe { &(*subvalue).value };
+        }
+        Some(rvalue)
+    }
+
+    // Check whether this is a leaf or leaf-list node.
+    fn is_term(&self) -> bool {
+        let snode = unsafe { (*self.raw).schema };
+        !snode.is_null()
+            && unsafe { (*snode).nodetype } as u32
+                & (ffi::LYS_LEAF | ffi::LYS_LEAFLIST)
+                != 0
+    }
+
+    // Check whether this is an anydata or anyxml node.
+    fn is_any(&self) -> bool {
+        let snode = unsafe { (*self.raw).schema };
+        !snode.is_null()
+            && unsafe { (*snode).nodetype } as u32
+                & (ffi::LYS_ANYDATA | ffi::LYS_ANYXML)
+                != 0
+    }
+
+    // Check whether this node (along with its subtree) can be moved under the
+    // given parent node of the same data tree.
+    fn check_move_to(&self, new_parent: &DataNodeRef<'_>) -> Result<()> {
+        let invalid = |msg: &str| {
+            Error::with_msg(ffi::LY_ERR::LY_EINVAL, msg, Some(self.path()))
+        };
+
+        if self.raw == self.tree.raw {
+            return Err(invalid(
+                "The first top-level node of a data tree can't be moved",
+            ));
+        }
+        if new_parent.inclusive_ancestors().any(|dnode| dnode == *self) {
+            return Err(invalid("A node can't be moved into its own subtree"));
+        }
+
+        // Check schema compatibility before unlinking the node.
+        let (snode, sparent) =
+            unsafe { ((*self.raw).schema, (*new_parent.raw).schema) };
+        if !snode.is_null() {
+            let expected = self.schema().ancestors().find(|snode| {
+                !matches!(
+                    snode.kind(),
+                    SchemaNodeKind::Choice
+                        | SchemaNodeKind::Case
+                        | SchemaNodeKind::Input
+                        | SchemaNodeKind::Output
+                )
+            });
+            let sparent = unsafe {
+                SchemaNode::from_raw_opt(self.context(), sparent as *mut _)
+            };
+            if expected != sparent {
+                return Err(invalid(
+                    "The new parent isn't the schema parent of the node",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Check whether this node can be moved right before or after the given
+    // anchor node.
+    fn check_move(&self, anchor: &DataNodeRef<'_>, before: bool) -> Result<()> {
+        let invalid = |msg: &str| {
+            Error::with_msg(ffi::LY_ERR::LY_EINVAL, msg, Some(self.path()))
+        };
+
+        let (snode, sanchor) =
+            unsafe { ((*self.raw).schema, (*anchor.raw).schema) };
+        if snode.is_null()
+            || snode != sanchor
+            || !self.schema().is_user_ordered()
+        {
+            return Err(invalid(
+                "Both nodes must be instances of the same user-ordered list \
+                 or leaf-list",
+            ));
+        }
+        if unsafe { (*self.raw).parent != (*anchor.raw).parent } {
+            return Err(invalid("Both nodes must have the same parent"));
+        }
+        if self.raw == anchor.raw {
+            return Err(invalid("A node can't be moved relative to itself"));
+        }
+        // The first top-level node of the data tree must stay in place.
+        if self.raw == self.tree.raw || (before && anchor.raw == self.tree.raw)
+        {
+            return Err(invalid(
+                "The first top-level node of a data tree can't be moved",
+            ));
+        }
+
+        Ok(())
+    }
+
+    // Walk over this node and its descendants.
+    fn walk_depth<V: DataVisitor<'a>>(&self, visitor: &mut V, depth: usize) {
+        visitor.enter(self, depth);
+        for child in self.children() {
+            child.walk_depth(visitor, depth + 1);
+        }
+        visitor.leave(self, depth);
+    }
 }
 
 impl<'a> Data for DataNodeRef<'a> {
@@ -870,6 +3260,13 @@ impl<'a> Data for DataNodeRef<'a> {
     fn raw(&self) -> *mut ffi::lyd_node {
         self.raw
     }
+
+    fn default_nodes(&self) -> impl Iterator<Item = DataNodeRef<'_>> {
+        // Unlike data trees, only the descendants of the node are considered.
+        Traverse::new(self.clone()).filter(
+            |dnode| unsafe { (*dnode.raw).flags } & ffi::LYD_DEFAULT != 0,
+        )
+    }
 }
 
 unsafe impl<'a> Binding<'a> for DataNodeRef<'a> {
@@ -931,58 +3328,594 @@ impl<'a> PartialEq for DataNodeRef<'a> {
 unsafe impl Send for DataNodeRef<'_> {}
 unsafe impl Sync for DataNodeRef<'_> {}
 
-// ===== impl Metadata =====
+// ===== impl Metadata =====
+
+impl<'a> Metadata<'a> {
+    /// Metadata name.
+    pub fn name(&self) -> &str {
+        char_ptr_to_str(unsafe { (*self.raw).name })
+    }
+
+    /// Module of the annotation this metadata is an instance of.
+    pub fn module(&self) -> SchemaModule<'_> {
+        let module = unsafe { (*(*self.raw).annotation).module };
+        unsafe { SchemaModule::from_raw(self.dnode.context(), module) }
+    }
+
+    /// Metadata value representation.
+    pub fn value(&self) -> &str {
+        let rvalue = unsafe { (*self.raw).value };
+        let mut canonical = rvalue._canonical;
+        if canonical.is_null() {
+            canonical = unsafe {
+                ffi::lyd_value_get_canonical(
+                    self.dnode.tree.context.raw,
+                    &rvalue,
+                )
+            };
+        }
+        char_ptr_to_str(canonical)
+    }
+
+    /// Metadata value (typed representation), according to the type of its
+    /// annotation.
+    ///
+    /// Returns `None` if the type of the value couldn't be resolved.
+    pub fn value_typed(&self) -> Option<DataValue> {
+        let rvalue = unsafe { &(*self.raw).value };
+        if rvalue.realtype.is_null() {
+            return None;
+        }
+        Some(unsafe { DataValue::from_raw(&self.dnode.tree.context, rvalue) })
+    }
+
+    /// Next metadata.
+    #[doc(hidden)]
+    pub(crate) fn next(&self) -> Option<Metadata<'a>> {
+        let rnext = unsafe { (*self.raw).next };
+        unsafe { Metadata::from_raw_opt(self.dnode, rnext) }
+    }
+}
+
+unsafe impl<'a> Binding<'a> for Metadata<'a> {
+    type CType = ffi::lyd_meta;
+    type Container = DataNodeRef<'a>;
+
+    unsafe fn from_raw(
+        dnode: &'a DataNodeRef<'_>,
+        raw: *mut ffi::lyd_meta,
+    ) -> Metadata<'a> {
+        Metadata { dnode, raw }
+    }
+}
+
+impl<'a> PartialEq for Metadata<'a> {
+    fn eq(&self, other: &Metadata<'_>) -> bool {
+        self.raw == other.raw
+    }
+}
+
+unsafe impl Send for Metadata<'_> {}
+unsafe impl Sync for Metadata<'_> {}
+
+// ===== impl Attribute =====
+
+impl<'a> Attribute<'a> {
+    /// Attribute name.
+    pub fn name(&self) -> &str {
+        char_ptr_to_str(unsafe { (*self.raw).name.name })
+    }
+
+    /// Attribute prefix, if any.
+    pub fn prefix(&self) -> Option<&str> {
+        char_ptr_to_opt_str(unsafe { (*self.raw).name.prefix })
+    }
+
+    /// Module name (JSON) or namespace (XML) of the attribute, if any.
+    pub fn module(&self) -> Option<&str> {
+        char_ptr_to_opt_str(unsafe {
+            (*self.raw).name.__bindgen_anon_1.module_name
+        })
+    }
+
+    /// Attribute value, as it was provided.
+    pub fn value(&self) -> &str {
+        char_ptr_to_str(unsafe { (*self.raw).value })
+    }
+
+    /// Next attribute.
+    #[doc(hidden)]
+    pub(crate) fn next(&self) -> Option<Attribute<'a>> {
+        let rnext = unsafe { (*self.raw).next };
+        unsafe { Attribute::from_raw_opt(self.dnode, rnext) }
+    }
+}
+
+unsafe impl<'a> Binding<'a> for Attribute<'a> {
+    type CType = ffi::lyd_attr;
+    type Container = DataNodeRef<'a>;
+
+    unsafe fn from_raw(
+        dnode: &'a DataNodeRef<'_>,
+        raw: *mut ffi::lyd_attr,
+    ) -> Attribute<'a> {
+        Attribute { dnode, raw }
+    }
+}
+
+impl<'a> PartialEq for Attribute<'a> {
+    fn eq(&self, other: &Attribute<'_>) -> bool {
+        self.raw == other.raw
+    }
+}
+
+unsafe impl Send for Attribute<'_> {}
+unsafe impl Sync for Attribute<'_> {}
+
+// ===== impl DataParser =====
+
+impl DataParser {
+    /// Create a new incremental data parser.
+    pub fn new(
+        context: &Arc<Context>,
+        format: DataFormat,
+        parser_options: DataParserFlags,
+        validation_options: DataValidationFlags,
+    ) -> DataParser {
+        DataParser {
+            context: context.clone(),
+            format,
+            parser_options,
+            validation_options,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Append a chunk of input data.
+    pub fn feed(&mut self, chunk: &[u8]) {
+        self.buffer.extend_from_slice(chunk);
+    }
+
+    /// Parse (and validate) all input data fed so far as a YANG data tree.
+    ///
+    /// LYB data isn't accepted, since libyang reads it without bounds
+    /// checking: use [`DataParser::finish_lyb`] instead.
+    pub fn finish(mut self) -> Result<DataTree> {
+        if self.format == DataFormat::LYB {
+            return Err(Error::with_msg(
+                ffi::LY_ERR::LY_EINVAL,
+                "LYB data must be parsed using DataParser::finish_lyb",
+                None,
+            ));
+        }
+
+        // The XML and JSON parsers expect a null-terminated string.
+        if self.buffer.contains(&0) {
+            return Err(Error::with_msg(
+                ffi::LY_ERR::LY_EINVAL,
+                "Input data contains a null byte",
+                None,
+            ));
+        }
+        self.buffer.push(0);
+
+        parse_data_mem(
+            &self.context,
+            self.buffer.as_ptr() as *const c_char,
+            self.format,
+            self.parser_options,
+            self.validation_options,
+        )
+    }
+
+    /// Parse (and validate) all LYB data fed so far as a YANG data tree.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as for [`DataTree::parse_lyb`] apply: the fed
+    /// chunks must add up to a complete LYB document. A stream cut short
+    /// can cause reads past the end of the internal buffer.
+    pub unsafe fn finish_lyb(self) -> Result<DataTree> {
+        if self.format != DataFormat::LYB {
+            return Err(Error::with_msg(
+                ffi::LY_ERR::LY_EINVAL,
+                "Input data isn't in the LYB format",
+                None,
+            ));
+        }
+
+        DataTree::parse_lyb(
+            &self.context,
+            &self.buffer,
+            self.parser_options,
+            self.validation_options,
+        )
+    }
+}
+
+// ===== impl DataStoreBuilder =====
+
+impl DataStoreBuilder {
+    /// Create a new data store builder.
+    pub fn new(context: &Arc<Context>) -> DataStoreBuilder {
+        DataStoreBuilder {
+            tree: DataTree::new(context),
+            modules: Vec::new(),
+        }
+    }
+
+    /// Add the data tree of the given module.
+    ///
+    /// An error is returned if the module was already added, if the data tree
+    /// belongs to a different context or if it contains top-level data of
+    /// other modules.
+    pub fn add(&mut self, module: &str, dtree: &DataTree) -> Result<()> {
+        let invalid = |msg: String, path: Option<String>| {
+            Error::with_msg(
+                ffi::LY_ERR::LY_EINVAL,
+                format!("Module \"{}\": {}", module, msg),
+                path,
+            )
+        };
+
+        if self.modules.iter().any(|name| name == module) {
+            return Err(invalid("data was already added".to_owned(), None));
+        }
+        if !Arc::ptr_eq(&self.tree.context, &dtree.context) {
+            return Err(invalid(
+                "data tree belongs to a different context".to_owned(),
+                None,
+            ));
+        }
+        for dnode in Siblings::new(dtree.reference()) {
+            if unsafe { (*dnode.raw).schema }.is_null() {
+                continue;
+            }
+            let owner = dnode.owner_module();
+            if owner.name() != module {
+                return Err(invalid(
+                    format!(
+                        "data tree contains data of module \"{}\"",
+                        owner.name()
+                    ),
+                    Some(dnode.path()),
+                ));
+            }
+        }
+
+        self.tree
+            .merge(dtree)
+            .map_err(|error| attribute_error(error, "Module", module))?;
+        self.modules.push(module.to_owned());
+
+        Ok(())
+    }
+
+    /// Validate the combined data tree and return it.
+    pub fn build(mut self, options: DataValidationFlags) -> Result<DataTree> {
+        if let Err(error) = self.tree.validate(options) {
+            // Attribute the error to the module of the top-level node in its
+            // path, when known.
+            let module = error.path.as_deref().and_then(|path| {
+                let step = path.trim_start_matches('/').split('/').next()?;
+                let (module, _) = step.split_once(':')?;
+                Some(module.to_owned())
+            });
+            return match module {
+                Some(module) if self.modules.contains(&module) => {
+                    Err(attribute_error(error, "Module", &module))
+                }
+                _ => Err(error),
+            };
+        }
+
+        Ok(self.tree)
+    }
+}
+
+// ===== impl YangPatch =====
+
+impl YangPatch {
+    /// Parse (and validate) a YANG Patch document encoded in JSON, whose
+    /// top-level object must be "ietf-yang-patch:yang-patch".
+    ///
+    /// The document is parsed by libyang using the "yang-patch" YANG data
+    /// template, so the ietf-yang-patch module must be implemented in the
+    /// context.
+    pub fn parse_json(context: &Arc<Context>, data: &str) -> Result<YangPatch> {
+        let ext = yang_patch_template(context).ok_or_else(|| {
+            Error::with_msg(
+                ffi::LY_ERR::LY_ENOTFOUND,
+                "The ietf-yang-patch module isn't implemented".to_owned(),
+                None,
+            )
+        })?;
+
+        let data = CString::new(data).unwrap();
+        let mut input = std::ptr::null_mut();
+        let ret = unsafe { ffi::ly_in_new_memory(data.as_ptr(), &mut input) };
+        if ret != ffi::LY_ERR::LY_SUCCESS {
+            return Err(Error::new(context));
+        }
+        let mut rnode = std::ptr::null_mut();
+        let ret = unsafe {
+            ffi::lyd_parse_ext_data(
+                ext,
+                std::ptr::null_mut(),
+                input,
+                DataFormat::JSON as u32,
+                DataParserFlags::STRICT.bits(),
+                0,
+                &mut rnode,
+            )
+        };
+        unsafe { ffi::ly_in_free(input, 0) };
+        if ret != ffi::LY_ERR::LY_SUCCESS {
+            return Err(Error::new(context));
+        }
+        let dtree = unsafe { DataTree::from_raw(context, rnode) };
+
+        let mut patch = YangPatch::default();
+        let dnode = match dtree.reference() {
+            Some(dnode) => dnode,
+            None => return Ok(patch),
+        };
+        for dnode in dnode.children() {
+            match dnode.schema().name() {
+                "patch-id" => {
+                    patch.patch_id = dnode.value_canonical().unwrap_or_default()
+                }
+                "comment" => patch.comment = dnode.value_canonical(),
+                "edit" => patch.edits.push(YangPatchEdit::from_data(&dnode)?),
+                _ => (),
+            }
+        }
+
+        Ok(patch)
+    }
+
+    /// Apply the patch to the given data tree.
+    ///
+    /// The patch target is a RESTCONF data resource identifier (e.g.
+    /// `/ietf-interfaces:interfaces`, or `/` for the whole data tree), which
+    /// the edit targets are relative to. The edits are applied in order on a
+    /// copy of the data tree, which is then validated using the provided
+    /// options. Only if all edits and the validation succeed, the edits are
+    /// applied to the data tree itself, which is validated in place (as in
+    /// [`DataTree::merge_validate`]). Errors of a given edit have its edit-id
+    /// prepended to their message.
+    pub fn apply(
+        &self,
+        dtree: &mut DataTree,
+        target: &str,
+        options: DataValidationFlags,
+    ) -> Result<()> {
+        let mut work = dtree.duplicate()?;
+        self.apply_edits(&mut work, target)?;
+        work.validate(options)?;
+
+        self.apply_edits(dtree, target)?;
+        dtree.validate(options)
+    }
+
+    // Apply all edits in order, without validating the result.
+    fn apply_edits(&self, dtree: &mut DataTree, target: &str) -> Result<()> {
+        for edit in &self.edits {
+            edit.apply(dtree, target).map_err(|error| {
+                attribute_error(error, "Edit", &edit.edit_id)
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+// ===== impl YangPatchEdit =====
+
+impl YangPatchEdit {
+    // Convert a parsed "edit" list entry.
+    fn from_data(dnode: &DataNodeRef<'_>) -> Result<YangPatchEdit> {
+        let mut edit = YangPatchEdit {
+            edit_id: String::new(),
+            operation: YangPatchOperation::Merge,
+            target: String::new(),
+            point: None,
+            position: None,
+            value: None,
+        };
+        for dnode in dnode.children() {
+            match dnode.schema().name() {
+                "edit-id" => {
+                    edit.edit_id = dnode.value_canonical().unwrap_or_default()
+                }
+                "operation" => {
+                    edit.operation = match dnode.value_canonical().as_deref() {
+                        Some("create") => YangPatchOperation::Create,
+                        Some("delete") => YangPatchOperation::Delete,
+                        Some("insert") => YangPatchOperation::Insert,
+                        Some("move") => YangPatchOperation::Move,
+                        Some("replace") => YangPatchOperation::Replace,
+                        Some("remove") => YangPatchOperation::Remove,
+                        _ => YangPatchOperation::Merge,
+                    }
+                }
+                "target" => {
+                    edit.target = dnode.value_canonical().unwrap_or_default()
+                }
+                "point" => edit.point = dnode.value_canonical(),
+                "where" => {
+                    edit.position = match dnode.value_canonical().as_deref() {
+                        Some("before") => Some(YangPatchWhere::Before),
+                        Some("after") => Some(YangPatchWhere::After),
+                        Some("first") => Some(YangPatchWhere::First),
+                        _ => Some(YangPatchWhere::Last),
+                    }
+                }
+                "value" => {
+                    edit.value = match dnode.any_value() {
+                        None => None,
+                        Some(DataAnyValue::DataTree(value)) => value
+                            .print_string(
+                                DataFormat::JSON,
+                                DataPrinterFlags::WITH_SIBLINGS
+                                    | DataPrinterFlags::SHRINK,
+                            )?,
+                        Some(DataAnyValue::Json(value)) => {
+                            Some(value.to_owned())
+                        }
+                        Some(_) => {
+                            return Err(Error::with_msg(
+                                ffi::LY_ERR::LY_EINVAL,
+                                "Unsupported edit value encoding".to_owned(),
+                                None,
+                            ))
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        Ok(edit)
+    }
+
+    // Apply the edit to the given data tree.
+    fn apply(&self, dtree: &mut DataTree, target: &str) -> Result<()> {
+        let context = dtree.context.clone();
+        let invalid = |errcode, msg: &str| Error::with_msg(errcode, msg, None);
+
+        let path = format!("{}{}", target.trim_end_matches('/'), self.target);
+        let steps = restconf_to_xpath(&context, &path)?;
+        let xpath = steps.concat();
+        let exists = !xpath.is_empty() && xpath_exists(dtree, &xpath)?;
+
+        match self.operation {
+            YangPatchOperation::Create | YangPatchOperation::Insert
+                if exists =>
+            {
+                return Err(invalid(
+                    ffi::LY_ERR::LY_EEXIST,
+                    "Data node already exists",
+                ));
+            }
+            YangPatchOperation::Delete | YangPatchOperation::Move
+                if !exists =>
+            {
+                return Err(invalid(
+                    ffi::LY_ERR::LY_ENOTFOUND,
+                    "Data node doesn't exist",
+                ));
+            }
+            _ => (),
+        }
+
+        match self.operation {
+            YangPatchOperation::Delete | YangPatchOperation::Remove => {
+                if exists {
+                    dtree.remove(&xpath)?;
+                }
+                return Ok(());
+            }
+            YangPatchOperation::Replace if exists => {
+                dtree.remove(&xpath)?;
+            }
+            _ => (),
+        }
 
-impl<'a> Metadata<'a> {
-    /// Metadata name.
-    pub fn name(&self) -> &str {
-        char_ptr_to_str(unsafe { (*self.raw).name })
-    }
+        if self.operation != YangPatchOperation::Move {
+            let value = self.value.as_deref().ok_or_else(|| {
+                invalid(ffi::LY_ERR::LY_EINVAL, "Missing edit value")
+            })?;
+            let source = edit_value(&context, &steps, value)?;
+            if xpath.is_empty() {
+                // The edit targets the whole data tree.
+                if self.operation == YangPatchOperation::Replace {
+                    while let Some(dnode) = dtree.reference() {
+                        let raw = dnode.raw;
+                        dtree.free_subtree(raw);
+                    }
+                    return dtree.merge(&source);
+                }
+            } else if !xpath_exists(&source, &xpath)? {
+                return Err(invalid(
+                    ffi::LY_ERR::LY_EINVAL,
+                    "Edit value doesn't match the edit target",
+                ));
+            }
+            dtree.merge(&source)?;
+        }
 
-    /// Metadata value representation.
-    pub fn value(&self) -> &str {
-        let rvalue = unsafe { (*self.raw).value };
-        let mut canonical = rvalue._canonical;
-        if canonical.is_null() {
-            canonical = unsafe {
-                ffi::lyd_value_get_canonical(
-                    self.dnode.tree.context.raw,
-                    &rvalue,
-                )
-            };
+        if matches!(
+            self.operation,
+            YangPatchOperation::Insert | YangPatchOperation::Move
+        ) {
+            self.position(dtree, target, &xpath)?;
         }
-        char_ptr_to_str(canonical)
-    }
 
-    /// Next metadata.
-    #[doc(hidden)]
-    pub(crate) fn next(&self) -> Option<Metadata<'a>> {
-        let rnext = unsafe { (*self.raw).next };
-        unsafe { Metadata::from_raw_opt(self.dnode, rnext) }
+        Ok(())
     }
-}
 
-unsafe impl<'a> Binding<'a> for Metadata<'a> {
-    type CType = ffi::lyd_meta;
-    type Container = DataNodeRef<'a>;
+    // Move the inserted or moved node to the requested position.
+    fn position(
+        &self,
+        dtree: &mut DataTree,
+        target: &str,
+        xpath: &str,
+    ) -> Result<()> {
+        let invalid =
+            |msg: &str| Error::with_msg(ffi::LY_ERR::LY_EINVAL, msg, None);
 
-    unsafe fn from_raw(
-        dnode: &'a DataNodeRef<'_>,
-        raw: *mut ffi::lyd_meta,
-    ) -> Metadata<'a> {
-        Metadata { dnode, raw }
-    }
-}
+        let dnode = dtree.find_path(xpath, false)?;
+        if unsafe { (*dnode.raw).schema }.is_null()
+            || !dnode.schema().is_user_ordered()
+        {
+            return Err(invalid(
+                "Only user-ordered list entries and leaf-list values can be \
+                 positioned",
+            ));
+        }
 
-impl<'a> PartialEq for Metadata<'a> {
-    fn eq(&self, other: &Metadata<'_>) -> bool {
-        self.raw == other.raw
+        let rschema = unsafe { (*dnode.raw).schema };
+        let mut instances = dnode
+            .parent()
+            .map(|parent| parent.children())
+            .unwrap_or_else(|| Siblings::new(dtree.reference()))
+            .filter(|sibling| unsafe { (*sibling.raw).schema } == rschema);
+        let (anchor, before) =
+            match self.position.unwrap_or(YangPatchWhere::Last) {
+                YangPatchWhere::First => {
+                    let first = instances.next().unwrap();
+                    if first == dnode {
+                        return Ok(());
+                    }
+                    (first, true)
+                }
+                YangPatchWhere::Last => {
+                    let last = instances.last().unwrap();
+                    if last == dnode {
+                        return Ok(());
+                    }
+                    (last, false)
+                }
+                position => {
+                    let point = self.point.as_deref().ok_or_else(|| {
+                        invalid("Missing point for the before/after position")
+                    })?;
+                    let path =
+                        format!("{}{}", target.trim_end_matches('/'), point);
+                    let xpath = restconf_to_xpath(dtree.context(), &path)?;
+                    let anchor = dtree.find_path(&xpath.concat(), false)?;
+                    (anchor, position == YangPatchWhere::Before)
+                }
+            };
+
+        let (path, anchor) = (dnode.path(), anchor.path());
+        dtree.move_sibling(&path, &anchor, before)?;
+
+        Ok(())
     }
 }
 
-unsafe impl Send for Metadata<'_> {}
-unsafe impl Sync for Metadata<'_> {}
-
 // ===== impl DataDiff =====
 
 impl DataDiff {
@@ -1006,21 +3939,87 @@ impl DataDiff {
     }
 
     /// Returns an iterator over the data changes.
-    pub fn iter(&self) -> impl Iterator<Item = (DataDiffOp, DataNodeRef<'_>)> {
+    ///
+    /// Each change is yielded along with a flag indicating whether it concerns
+    /// a default node. For replaced nodes, the flag is read from the
+    /// `yang:orig-default` metadata and tells whether the previous value was
+    /// the default one. For created and deleted nodes, it tells whether the
+    /// node itself is a default node. Default nodes are only present in diffs
+    /// created with the [`DataDiffFlags::DEFAULTS`] option.
+    pub fn iter(
+        &self,
+    ) -> impl Iterator<Item = (DataDiffOp, DataNodeRef<'_>, bool)> {
         self.tree.traverse().filter_map(|dnode| {
-            match dnode.meta().find(|meta| meta.name() == "operation") {
+            let op = match dnode.get_meta("operation") {
                 Some(meta) => match meta.value() {
-                    "create" => Some((DataDiffOp::Create, dnode)),
-                    "delete" => Some((DataDiffOp::Delete, dnode)),
-                    "replace" => Some((DataDiffOp::Replace, dnode)),
-                    "none" => None,
+                    "create" => DataDiffOp::Create,
+                    "delete" => DataDiffOp::Delete,
+                    "replace" => DataDiffOp::Replace,
+                    "none" => return None,
                     _ => unreachable!(),
                 },
-                None => None,
+                None => return None,
+            };
+            let orig_default = dnode.find_meta(Some("yang"), "orig-default");
+            let is_default = match orig_default {
+                Some(meta) => meta.value() == "true",
+                None => {
+                    let flags = unsafe { (*dnode.raw).flags };
+                    flags & ffi::LYD_DEFAULT != 0
+                }
+            };
+            Some((op, dnode, is_default))
+        })
+    }
+
+    /// Returns an iterator over the data changes, including the values of the
+    /// changed nodes before and after each change.
+    ///
+    /// The old value of replaced leafs is taken from the "orig-value"
+    /// metadata.
+    pub fn changes(&self) -> impl Iterator<Item = DataDiffChange<'_>> {
+        self.iter().map(|(op, dnode, is_default)| {
+            let value = if dnode.is_any() {
+                dnode.any_value_string().ok().flatten()
+            } else {
+                dnode.value_canonical()
+            };
+            let (old_value, new_value) = match op {
+                DataDiffOp::Create => (None, value),
+                DataDiffOp::Delete => (value, None),
+                DataDiffOp::Replace => {
+                    let old_value = dnode
+                        .find_meta(Some("yang"), "orig-value")
+                        .map(|meta| meta.value().to_owned());
+                    (old_value, value)
+                }
+            };
+            DataDiffChange {
+                op,
+                node: dnode,
+                is_default,
+                old_value,
+                new_value,
             }
         })
     }
 
+    /// Returns the number of data changes of each type.
+    ///
+    /// Only the changes yielded by [`DataDiff::iter`] are counted, so a
+    /// created or deleted subtree counts as a single change.
+    pub fn summary(&self) -> DiffSummary {
+        let mut summary = DiffSummary::default();
+        for (op, _, _) in self.iter() {
+            match op {
+                DataDiffOp::Create => summary.created += 1,
+                DataDiffOp::Delete => summary.deleted += 1,
+                DataDiffOp::Replace => summary.replaced += 1,
+            }
+        }
+        summary
+    }
+
     /// Reverse a diff and make the opposite changes. Meaning change create to
     /// delete, delete to create, or move from place A to B to move from B
     /// to A and so on.
@@ -1040,6 +4039,20 @@ impl DataDiff {
     }
 }
 
+/// Two data diffs are equal when they contain the same nodes with the same
+/// metadata (e.g. the diff operations), regardless of the relative order of
+/// sibling nodes.
+impl PartialEq for DataDiff {
+    fn eq(&self, other: &DataDiff) -> bool {
+        compare_siblings_unordered(
+            self.tree.reference(),
+            other.tree.reference(),
+            0,
+            true,
+        )
+    }
+}
+
 impl Data for DataDiff {
     fn tree(&self) -> &DataTree {
         &self.tree
@@ -1049,3 +4062,751 @@ impl Data for DataDiff {
         self.tree.raw
     }
 }
+
+// ===== impl DiffSummary =====
+
+impl DiffSummary {
+    /// Returns the total number of changes.
+    pub fn total(&self) -> usize {
+        self.created + self.deleted + self.replaced
+    }
+}
+
+/// Formats the summary as "3 created, 1 deleted, 2 replaced".
+impl fmt::Display for DiffSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} created, {} deleted, {} replaced",
+            self.created, self.deleted, self.replaced
+        )
+    }
+}
+
+// ===== impl ByContent =====
+
+impl PartialEq for ByContent<'_> {
+    fn eq(&self, other: &ByContent<'_>) -> bool {
+        self.0.equivalent(other.0, DataCompareFlags::empty())
+    }
+}
+
+impl Eq for ByContent<'_> {}
+
+// ===== impl NodeDescription =====
+
+impl fmt::Display for NodeDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path)?;
+        match self.node_type {
+            DataNodeType::Schema(kind) => write!(f, " ({:?}", kind)?,
+            DataNodeType::Opaque => write!(f, " (Opaque")?,
+        }
+        match (&self.type_name, &self.value_type) {
+            (Some(type_name), _) => write!(f, ", {}", type_name)?,
+            (None, Some(value_type)) => write!(f, ", {:?}", value_type)?,
+            (None, None) => (),
+        }
+        write!(f, ")")?;
+        if let Some(value) = &self.value {
+            write!(f, " = {}", value)?;
+        }
+        if self.is_default {
+            write!(f, " [default]")?;
+        }
+        Ok(())
+    }
+}
+
+// ===== helper functions =====
+
+// Prepend the item (e.g. module or edit) responsible for the given error to
+// its message.
+fn attribute_error(mut error: Error, kind: &str, name: &str) -> Error {
+    let msg = error.msg.take().unwrap_or_default();
+    error.msg = Some(format!("{} \"{}\": {}", kind, name, msg));
+    error
+}
+
+// 64-bit FNV-1a parameters.
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+// Type of the typed user data stored in the private pointer of data nodes.
+type UserData = Box<dyn Any + Send + Sync>;
+
+// Drop the typed user data attached to the given data node, if any.
+unsafe fn free_user_data(dnode: *mut ffi::lyd_node) {
+    let priv_ = (*dnode).priv_ as *mut UserData;
+    if !priv_.is_null() {
+        drop(Box::from_raw(priv_));
+        (*dnode).priv_ = std::ptr::null_mut();
+    }
+}
+
+// Data nodes selected by a subtree filter.
+enum FilterMatch<'a> {
+    // The whole subtree is selected.
+    All,
+    // Only the given child nodes are selected (in no particular order).
+    Nodes(Vec<(DataNodeRef<'a>, FilterMatch<'a>)>),
+}
+
+impl FilterMatch<'_> {
+    // Combine the selections of two filters (logical OR).
+    fn union(self, other: Self) -> Self {
+        match (self, other) {
+            (FilterMatch::Nodes(mut nodes), FilterMatch::Nodes(others)) => {
+                for (dnode, selection) in others {
+                    match nodes.iter().position(|(node, _)| *node == dnode) {
+                        Some(pos) => {
+                            let (node, current) = nodes.swap_remove(pos);
+                            nodes.push((node, current.union(selection)));
+                        }
+                        None => nodes.push((dnode, selection)),
+                    }
+                }
+                FilterMatch::Nodes(nodes)
+            }
+            _ => FilterMatch::All,
+        }
+    }
+}
+
+// Evaluate a sibling set of subtree filter nodes against a sibling set of data
+// nodes (RFC 6241, section 6.2). `None` is returned when nothing is selected.
+fn subtree_filter<'a>(
+    filters: &[DataNodeRef<'_>],
+    data: Siblings<'a, DataNodeRef<'a>>,
+) -> Option<FilterMatch<'a>> {
+    let data = data.collect::<Vec<_>>();
+    let content_match = |filter: &DataNodeRef<'_>, dnode: &DataNodeRef<'_>| {
+        let value = filter_value(filter);
+        value.is_some()
+            && filter_node_matches(filter, dnode)
+            && dnode.value_canonical() == value
+    };
+
+    // All content match nodes must match for the sibling set to be selected.
+    let (content, others): (Vec<_>, Vec<_>) = filters
+        .iter()
+        .partition(|filter| filter_value(filter).is_some());
+    if !content
+        .iter()
+        .all(|filter| data.iter().any(|dnode| content_match(filter, dnode)))
+    {
+        return None;
+    }
+    // Without selection or containment nodes, everything is selected.
+    if others.is_empty() {
+        return Some(FilterMatch::All);
+    }
+
+    let mut nodes = Vec::new();
+    for dnode in data {
+        let mut selection = None;
+        if content.iter().any(|filter| content_match(filter, &dnode)) {
+            selection = Some(FilterMatch::All);
+        }
+        for filter in others
+            .iter()
+            .filter(|filter| filter_node_matches(filter, &dnode))
+        {
+            let children = filter.children().collect::<Vec<_>>();
+            let child_selection = if children.is_empty() {
+                // Selection node.
+                Some(FilterMatch::All)
+            } else {
+                // Containment node.
+                subtree_filter(&children, dnode.children())
+            };
+            selection = match (selection, child_selection) {
+                (Some(selection), Some(child)) => Some(selection.union(child)),
+                (selection, child) => selection.or(child),
+            };
+        }
+        if let Some(selection) = selection {
+            nodes.push((dnode, selection));
+        }
+    }
+
+    if nodes.is_empty() {
+        None
+    } else {
+        Some(FilterMatch::Nodes(nodes))
+    }
+}
+
+// Returns the value of a subtree filter node, if it's a content match node
+// (a leaf or leaf-list with a non-empty value).
+fn filter_value(filter: &DataNodeRef<'_>) -> Option<String> {
+    if filter.first_child().is_some() {
+        return None;
+    }
+    let value = if unsafe { (*filter.raw).schema }.is_null() {
+        let ropaq = filter.raw as *mut ffi::lyd_node_opaq;
+        char_ptr_to_opt_string(unsafe { (*ropaq).value })
+    } else {
+        filter.value_canonical()
+    };
+    value.filter(|value| !value.is_empty())
+}
+
+// Check whether a subtree filter node matches the given data node, comparing
+// their names and modules. Filter nodes without a module match any module.
+fn filter_node_matches(
+    filter: &DataNodeRef<'_>,
+    dnode: &DataNodeRef<'_>,
+) -> bool {
+    if unsafe { (*dnode.raw).schema }.is_null() {
+        return false;
+    }
+    let snode = dnode.schema();
+    let module = snode.module();
+
+    if unsafe { (*filter.raw).schema }.is_null() {
+        let ropaq = filter.raw as *mut ffi::lyd_node_opaq;
+        let name = char_ptr_to_str(unsafe { (*ropaq).name.name });
+        let filter_module = char_ptr_to_opt_str(unsafe {
+            (*ropaq).name.__bindgen_anon_1.module_name
+        });
+        let expected = if unsafe { (*ropaq).format }
+            == ffi::LY_VALUE_FORMAT::LY_VALUE_XML
+        {
+            module.namespace()
+        } else {
+            module.name()
+        };
+        name == snode.name()
+            && (filter_module.is_none() || filter_module == Some(expected))
+    } else {
+        let fnode = filter.schema();
+        fnode.name() == snode.name() && fnode.module() == module
+    }
+}
+
+// Duplicate the data selected by a subtree filter, optionally as a child of
+// the given parent, and return the duplicated node.
+fn subtree_filter_dup(
+    dnode: &DataNodeRef<'_>,
+    selection: &FilterMatch<'_>,
+    parent: *mut ffi::lyd_node,
+) -> Result<*mut ffi::lyd_node> {
+    let mut options = ffi::LYD_DUP_WITH_FLAGS;
+    if let FilterMatch::All = selection {
+        options |= ffi::LYD_DUP_RECURSIVE;
+    }
+    let mut dup = std::ptr::null_mut();
+    let ret = unsafe {
+        ffi::lyd_dup_single(
+            dnode.raw,
+            parent as *mut ffi::lyd_node_inner,
+            options,
+            &mut dup,
+        )
+    };
+    if ret != ffi::LY_ERR::LY_SUCCESS {
+        return Err(Error::new(dnode.context()));
+    }
+
+    if let FilterMatch::Nodes(nodes) = selection {
+        for child in dnode.children() {
+            let selection = match nodes.iter().find(|(node, _)| *node == child)
+            {
+                Some((_, selection)) => selection,
+                None => continue,
+            };
+            // List keys are always duplicated along with their list entry.
+            if child.schema().is_list_key() {
+                continue;
+            }
+            if let Err(error) = subtree_filter_dup(&child, selection, dup) {
+                if parent.is_null() {
+                    unsafe { ffi::lyd_free_tree(dup) };
+                }
+                return Err(error);
+            }
+        }
+    }
+
+    Ok(dup)
+}
+
+// State of an ongoing chunked print.
+struct PrintChunks<'a> {
+    f: &'a mut dyn FnMut(&[u8]) -> io::Result<()>,
+    error: Option<io::Error>,
+}
+
+// Write callback of chunked prints. Once the user callback fails, all further
+// writes fail immediately.
+unsafe extern "C" fn ly_write_chunk_cb(
+    user_data: *mut c_void,
+    buf: *const c_void,
+    count: usize,
+) -> isize {
+    let state = &mut *(user_data as *mut PrintChunks<'_>);
+    if state.error.is_some() {
+        return -1;
+    }
+
+    let chunk = if count == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(buf as *const u8, count)
+    };
+    match (state.f)(chunk) {
+        Ok(()) => count as isize,
+        Err(error) => {
+            state.error = Some(error);
+            -1
+        }
+    }
+}
+
+// Compare two lists of sibling data nodes recursively, regardless of the order
+// in which the nodes appear. Optionally, the metadata of the nodes is compared
+// as well.
+fn compare_siblings_unordered(
+    dnode1: Option<DataNodeRef<'_>>,
+    dnode2: Option<DataNodeRef<'_>>,
+    options: u32,
+    meta: bool,
+) -> bool {
+    let siblings1 = Siblings::new(dnode1).collect::<Vec<_>>();
+    let mut siblings2 = Siblings::new(dnode2).collect::<Vec<_>>();
+    if siblings1.len() != siblings2.len() {
+        return false;
+    }
+
+    for dnode1 in siblings1 {
+        let pos = siblings2.iter().position(|dnode2| {
+            let ret = unsafe {
+                ffi::lyd_compare_single(dnode1.raw, dnode2.raw, options)
+            };
+            ret == ffi::LY_ERR::LY_SUCCESS
+                && (!meta || compare_meta(&dnode1, dnode2))
+                && compare_siblings_unordered(
+                    dnode1.first_child(),
+                    dnode2.first_child(),
+                    options,
+                    meta,
+                )
+        });
+        match pos {
+            Some(pos) => {
+                siblings2.swap_remove(pos);
+            }
+            None => return false,
+        }
+    }
+
+    true
+}
+
+// Compare the metadata of two data nodes, regardless of their order.
+fn compare_meta(dnode1: &DataNodeRef<'_>, dnode2: &DataNodeRef<'_>) -> bool {
+    let meta_list = |dnode: &DataNodeRef<'_>| {
+        let mut list = dnode
+            .meta()
+            .map(|meta| {
+                (
+                    meta.module().name().to_owned(),
+                    meta.name().to_owned(),
+                    meta.value().to_owned(),
+                )
+            })
+            .collect::<Vec<_>>();
+        list.sort();
+        list
+    };
+
+    meta_list(dnode1) == meta_list(dnode2)
+}
+
+// Convert a RESTCONF data resource identifier (RFC 8040, section 3.5.3) to
+// the steps of the equivalent data path, with predicates for the list keys
+// and leaf-list values.
+fn restconf_to_xpath(context: &Context, path: &str) -> Result<Vec<String>> {
+    let invalid = |msg: String| {
+        Error::with_msg(ffi::LY_ERR::LY_EINVAL, msg, Some(path.to_owned()))
+    };
+
+    let mut steps = Vec::new();
+    let mut schema_path = String::new();
+    for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+        let (name, keys) = match segment.split_once('=') {
+            Some((name, keys)) => (name, Some(keys)),
+            None => (segment, None),
+        };
+        schema_path.push('/');
+        schema_path.push_str(name);
+
+        let mut step = format!("/{}", name);
+        if let Some(keys) = keys {
+            let values = keys
+                .split(',')
+                .map(|value| {
+                    percent_decode(value).ok_or_else(|| {
+                        invalid(format!("Invalid encoding in \"{}\"", value))
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let snode = context.find_path(&schema_path)?;
+            let names = match snode.kind() {
+                SchemaNodeKind::List => snode
+                    .list_keys()
+                    .map(|snode| snode.name().to_owned())
+                    .collect(),
+                SchemaNodeKind::LeafList => vec![".".to_owned()],
+                _ => {
+                    return Err(invalid(format!(
+                        "\"{}\" isn't a list or leaf-list",
+                        name
+                    )))
+                }
+            };
+            if names.len() != values.len() {
+                return Err(invalid(format!(
+                    "Wrong number of keys for \"{}\"",
+                    name
+                )));
+            }
+            for (name, value) in names.iter().zip(values) {
+                let literal = xpath_literal(&value).ok_or_else(|| {
+                    invalid(format!("Unsupported value \"{}\"", value))
+                })?;
+                step.push_str(&format!("[{}={}]", name, literal));
+            }
+        }
+        steps.push(step);
+    }
+
+    Ok(steps)
+}
+
+// Returns the prefix of the module with the given name in the context of a
+// module, which is either the module's own prefix or the prefix of one of its
+// imports (submodules included).
+fn import_prefix<'a>(
+    rmodule: *const ffi::lys_module,
+    name: &str,
+) -> Option<&'a str> {
+    if char_ptr_to_str(unsafe { (*rmodule).name }) == name {
+        return Some(char_ptr_to_str(unsafe { (*rmodule).prefix }));
+    }
+
+    let pmod = unsafe { (*rmodule).parsed };
+    if pmod.is_null() {
+        return None;
+    }
+    let includes = unsafe { sized_array((*pmod).includes) };
+    std::iter::once(unsafe { (*pmod).imports })
+        .chain(
+            includes
+                .iter()
+                .filter(|include| !include.submodule.is_null())
+                .map(|include| unsafe { (*include.submodule).imports }),
+        )
+        .flat_map(|rimports| unsafe { sized_array(rimports) })
+        .find(|import| char_ptr_to_str(import.name) == name)
+        .map(|import| char_ptr_to_str(import.prefix))
+}
+
+// Convert a canonical instance-identifier, whose node names are only prefixed
+// by their module name when it changes, to one where all node names are
+// prefixed using the given module name to prefix conversion.
+fn instance_id_with_prefixes<'a>(
+    path: &str,
+    prefix: impl Fn(&str) -> Option<&'a str>,
+) -> Option<String> {
+    let mut output = String::with_capacity(path.len());
+    let mut module = "";
+    let mut chars = path.char_indices().peekable();
+    while let Some((pos, c)) = chars.next() {
+        output.push(c);
+        match c {
+            // Node name of a path step or of a key predicate.
+            '/' | '[' => {
+                let start = pos + 1;
+                let mut end = start;
+                while let Some((pos, c)) = chars.peek() {
+                    if "/[]=".contains(*c) {
+                        break;
+                    }
+                    end = pos + c.len_utf8();
+                    chars.next();
+                }
+                let name = &path[start..end];
+                // Leaf-list value and positional predicates have no name.
+                if name.is_empty()
+                    || name == "."
+                    || name.starts_with(|c: char| c.is_ascii_digit())
+                {
+                    output.push_str(name);
+                    continue;
+                }
+                let name = match name.split_once(':') {
+                    Some((name_module, name)) => {
+                        module = name_module;
+                        name
+                    }
+                    None => name,
+                };
+                output.push_str(prefix(module)?);
+                output.push(':');
+                output.push_str(name);
+            }
+            // Quoted values are copied verbatim.
+            '\'' | '"' => {
+                for (_, quoted) in chars.by_ref() {
+                    output.push(quoted);
+                    if quoted == c {
+                        break;
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    Some(output)
+}
+
+// Check whether the given data path matches any node of the data tree.
+fn xpath_exists(dtree: &DataTree, xpath: &str) -> Result<bool> {
+    if dtree.raw.is_null() {
+        return Ok(false);
+    }
+    Ok(dtree.find_xpath(xpath)?.count() > 0)
+}
+
+// Decode a percent-encoded string.
+fn percent_decode(value: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut input = value.bytes();
+    while let Some(byte) = input.next() {
+        if byte == b'%' {
+            let hex = [input.next()?, input.next()?];
+            if !hex.iter().all(u8::is_ascii_hexdigit) {
+                return None;
+            }
+            let hex = std::str::from_utf8(&hex).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+// Quote a string as an XPath literal, which can't contain both kinds of
+// quotes.
+fn xpath_literal(value: &str) -> Option<String> {
+    if !value.contains('\'') {
+        Some(format!("'{}'", value))
+    } else if !value.contains('"') {
+        Some(format!("\"{}\"", value))
+    } else {
+        None
+    }
+}
+
+// Find the "yang-patch" YANG data template of the ietf-yang-patch module.
+fn yang_patch_template(
+    context: &Arc<Context>,
+) -> Option<*const ffi::lysc_ext_instance> {
+    let module = context.get_module_implemented("ietf-yang-patch")?;
+    let compiled = unsafe { (*module.raw).compiled };
+    if compiled.is_null() {
+        return None;
+    }
+    unsafe { sized_array((*compiled).exts) }
+        .iter()
+        .find(|ext| {
+            char_ptr_to_str(unsafe { (*ext.def).name }) == "yang-data"
+                && char_ptr_to_opt_str(ext.argument) == Some("yang-patch")
+        })
+        .map(|ext| ext as *const ffi::lysc_ext_instance)
+}
+
+// Parse the JSON value of a YANG Patch edit, whose target is identified by
+// the given data path steps, along with the parents of the target.
+fn edit_value(
+    context: &Arc<Context>,
+    steps: &[String],
+    value: &str,
+) -> Result<DataTree> {
+    let parser_options =
+        DataParserFlags::NO_VALIDATION | DataParserFlags::STRICT;
+    if steps.len() < 2 {
+        return DataTree::parse_string(
+            context,
+            value,
+            DataFormat::JSON,
+            parser_options,
+            DataValidationFlags::empty(),
+        );
+    }
+
+    let mut dtree = DataTree::new(context);
+    let parent = steps[..steps.len() - 1].concat();
+    dtree.new_path(&parent, None, false)?;
+    let parent = dtree.find_path(&parent, false)?.raw;
+
+    let value = CString::new(value).unwrap();
+    let mut input = std::ptr::null_mut();
+    let ret = unsafe { ffi::ly_in_new_memory(value.as_ptr(), &mut input) };
+    if ret != ffi::LY_ERR::LY_SUCCESS {
+        return Err(Error::new(context));
+    }
+    let ret = unsafe {
+        ffi::lyd_parse_data(
+            context.raw,
+            parent,
+            input,
+            DataFormat::JSON as u32,
+            parser_options.bits(),
+            0,
+            std::ptr::null_mut(),
+        )
+    };
+    unsafe { ffi::ly_in_free(input, 0) };
+    if ret != ffi::LY_ERR::LY_SUCCESS {
+        return Err(Error::new(context));
+    }
+
+    Ok(dtree)
+}
+
+// Check that the given data starts with a LYB header.
+fn check_lyb_header(data: &[u8]) -> Result<()> {
+    // The LYB header consists of the "lyb" magic number, a version byte and
+    // the hash of the context modules.
+    if data.len() < 8 || &data[0..3] != b"lyb" {
+        return Err(Error::with_msg(
+            ffi::LY_ERR::LY_EINVAL,
+            "Invalid LYB data",
+            None,
+        ));
+    }
+
+    Ok(())
+}
+
+// Parse data from a memory buffer, which must be null-terminated unless it's
+// in the LYB format.
+fn parse_data_mem(
+    context: &Arc<Context>,
+    data: *const c_char,
+    format: DataFormat,
+    parser_options: DataParserFlags,
+    validation_options: DataValidationFlags,
+) -> Result<DataTree> {
+    let mut input = std::ptr::null_mut();
+    let input_ptr = &mut input;
+    let ret = unsafe { ffi::ly_in_new_memory(data, input_ptr) };
+    if ret != ffi::LY_ERR::LY_SUCCESS {
+        return Err(Error::new(context));
+    }
+
+    let mut rnode = std::ptr::null_mut();
+    let rnode_ptr = &mut rnode;
+    let ret = unsafe {
+        ffi::lyd_parse_data(
+            context.raw,
+            std::ptr::null_mut(),
+            input,
+            format as u32,
+            parser_options.bits(),
+            validation_options.bits(),
+            rnode_ptr,
+        )
+    };
+    unsafe { ffi::ly_in_free(input, 0) };
+    if ret != ffi::LY_ERR::LY_SUCCESS {
+        return Err(Error::new(context));
+    }
+
+    Ok(unsafe { DataTree::from_raw(context, rnode) })
+}
+
+// Enclose printed data in the RESTCONF "data" object/element.
+fn restconf_wrap(data: &str, format: DataFormat, shrink: bool) -> String {
+    let data = data.trim();
+    let (begin, end, content) = match format {
+        DataFormat::JSON => {
+            // Remove the top-level braces.
+            let content = data
+                .strip_prefix('{')
+                .and_then(|data| data.strip_suffix('}'))
+                .unwrap_or(data)
+                .trim_matches('\n');
+            if shrink {
+                ("{\"ietf-restconf:data\":{", "}}", content)
+            } else {
+                ("{\n  \"ietf-restconf:data\": {", "  }\n}", content)
+            }
+        }
+        _ => (
+            "<data xmlns=\"urn:ietf:params:xml:ns:yang:ietf-restconf\">",
+            "</data>",
+            data,
+        ),
+    };
+
+    if shrink {
+        return format!("{}{}{}", begin, content, end);
+    }
+
+    let mut output = String::from(begin);
+    output.push('\n');
+    for line in content.lines() {
+        output.push_str("  ");
+        output.push_str(line);
+        output.push('\n');
+    }
+    output.push_str(end);
+    output.push('\n');
+    output
+}
+
+// Create a new opaque node, optionally as a child of the given parent.
+fn new_opaq(
+    context: &Context,
+    parent: *mut ffi::lyd_node,
+    name: &str,
+    value: Option<&str>,
+    module_ns: &str,
+) -> Result<*mut ffi::lyd_node> {
+    let name = CString::new(name).unwrap();
+    let module_ns = CString::new(module_ns).unwrap();
+    let value_cstr;
+    let mut rnode = std::ptr::null_mut();
+    let rnode_ptr = &mut rnode;
+
+    let value_ptr = match value {
+        Some(value) => {
+            value_cstr = CString::new(value).unwrap();
+            value_cstr.as_ptr()
+        }
+        None => std::ptr::null(),
+    };
+
+    let ret = unsafe {
+        ffi::lyd_new_opaq2(
+            parent,
+            context.raw,
+            name.as_ptr(),
+            value_ptr,
+            std::ptr::null(),
+            module_ns.as_ptr(),
+            rnode_ptr,
+        )
+    };
+    if ret != ffi::LY_ERR::LY_SUCCESS {
+        return Err(Error::new(context));
+    }
+
+    Ok(rnode)
+}
@@ -4,10 +4,16 @@
 // SPDX-License-Identifier: MIT
 //
 
+use std::sync::Arc;
+
 use crate::context::Context;
+use crate::data::DataTree;
 use crate::utils::*;
 use libyang2_sys as ffi;
 
+// XML namespace of the NETCONF base protocol.
+const NETCONF_BASE_NS: &str = "urn:ietf:params:xml:ns:netconf:base:1.0";
+
 /// A convenience wrapper around `Result` for `yang2::Error`.
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -15,14 +21,43 @@ pub type Result<T> = std::result::Result<T, Error>;
 #[derive(Debug, Eq, PartialEq)]
 pub struct Error {
     pub errcode: ffi::LY_ERR::Type,
+    vecode: ffi::LY_VECODE::Type,
     pub msg: Option<String>,
     pub path: Option<String>,
     pub apptag: Option<String>,
 }
 
+/// High-level categories of errors, derived from the libyang error code and
+/// validation error code of an [`Error`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ErrorKind {
+    /// The requested item (e.g. module or data node) wasn't found.
+    NotFound,
+    /// The item already exists.
+    AlreadyExists,
+    /// Invalid value or argument.
+    InvalidValue,
+    /// Malformed input (YANG, YIN, XML or JSON syntax error).
+    SyntaxError,
+    /// Schema or data validation failed (e.g. semantic error, invalid
+    /// reference or XPath expression).
+    ValidationFailed,
+    /// The operation isn't allowed.
+    Denied,
+    /// Out of memory.
+    OutOfMemory,
+    /// System call failure.
+    System,
+    /// Internal libyang error, including errors from type plugins.
+    Internal,
+    /// Any other error.
+    Other,
+}
+
 impl Error {
     pub fn new(ctx: &Context) -> Error {
         let errcode = unsafe { ffi::ly_errcode(ctx.raw) };
+        let vecode = unsafe { ffi::ly_vecode(ctx.raw) };
         let msg = unsafe { ffi::ly_errmsg(ctx.raw) };
         let msg = char_ptr_to_opt_string(msg);
         let path = unsafe { ffi::ly_errpath(ctx.raw) };
@@ -32,21 +67,136 @@ impl Error {
 
         Self {
             errcode,
+            vecode,
             msg,
             path,
             apptag,
         }
     }
+
+    // Create an error that isn't reported by libyang.
+    pub(crate) fn with_msg(
+        errcode: ffi::LY_ERR::Type,
+        msg: impl Into<String>,
+        path: Option<String>,
+    ) -> Error {
+        Self {
+            errcode,
+            vecode: ffi::LY_VECODE::LYVE_SUCCESS,
+            msg: Some(msg.into()),
+            path,
+            apptag: None,
+        }
+    }
+
+    /// Returns the libyang validation error code, which is only meaningful
+    /// for `LY_EVALID` errors.
+    pub fn vecode(&self) -> ffi::LY_VECODE::Type {
+        self.vecode
+    }
+
+    /// Returns the category of the error.
+    pub fn kind(&self) -> ErrorKind {
+        match self.errcode {
+            ffi::LY_ERR::LY_ENOTFOUND => ErrorKind::NotFound,
+            ffi::LY_ERR::LY_EEXIST => ErrorKind::AlreadyExists,
+            ffi::LY_ERR::LY_EINVAL => ErrorKind::InvalidValue,
+            ffi::LY_ERR::LY_EVALID => match self.vecode {
+                ffi::LY_VECODE::LYVE_SYNTAX
+                | ffi::LY_VECODE::LYVE_SYNTAX_YANG
+                | ffi::LY_VECODE::LYVE_SYNTAX_YIN
+                | ffi::LY_VECODE::LYVE_SYNTAX_XML
+                | ffi::LY_VECODE::LYVE_SYNTAX_JSON => ErrorKind::SyntaxError,
+                _ => ErrorKind::ValidationFailed,
+            },
+            ffi::LY_ERR::LY_EDENIED => ErrorKind::Denied,
+            ffi::LY_ERR::LY_EMEM => ErrorKind::OutOfMemory,
+            ffi::LY_ERR::LY_ESYS => ErrorKind::System,
+            ffi::LY_ERR::LY_EINT | ffi::LY_ERR::LY_EPLUGIN => {
+                ErrorKind::Internal
+            }
+            _ => ErrorKind::Other,
+        }
+    }
+
+    /// Convert the error to a NETCONF `rpc-error` element (RFC 6241, section
+    /// 4.3).
+    ///
+    /// The returned data tree consists of opaque nodes in the NETCONF base
+    /// namespace, since `rpc-error` isn't defined by any YANG module. The
+    /// error type and tag are derived from the error kind and application
+    /// tag, following the error reporting rules of RFC 7950 (section 15) for
+    /// validation errors. The path, application tag and message of the error
+    /// are included when known.
+    pub fn to_rpc_error(&self, context: &Arc<Context>) -> Result<DataTree> {
+        let (error_type, error_tag) = self.rpc_error_tag();
+        let mut dtree = DataTree::new(context);
+        let rpc_error = dtree
+            .new_opaq(None, "rpc-error", None, NETCONF_BASE_NS)?
+            .path();
+
+        for (name, value) in [
+            ("error-type", Some(error_type)),
+            ("error-tag", Some(error_tag)),
+            ("error-severity", Some("error")),
+            ("error-app-tag", self.apptag.as_deref()),
+            ("error-path", self.path.as_deref()),
+            ("error-message", self.msg.as_deref()),
+        ] {
+            if value.is_some() {
+                dtree.new_opaq(
+                    Some(&rpc_error),
+                    name,
+                    value,
+                    NETCONF_BASE_NS,
+                )?;
+            }
+        }
+
+        Ok(dtree)
+    }
+
+    // Returns the NETCONF error type and error tag corresponding to the error.
+    fn rpc_error_tag(&self) -> (&'static str, &'static str) {
+        match self.kind() {
+            ErrorKind::SyntaxError => ("rpc", "malformed-message"),
+            ErrorKind::NotFound => ("application", "data-missing"),
+            ErrorKind::AlreadyExists => ("application", "data-exists"),
+            ErrorKind::InvalidValue => ("application", "invalid-value"),
+            ErrorKind::ValidationFailed => match self.apptag.as_deref() {
+                Some("instance-required") | Some("missing-choice") => {
+                    ("application", "data-missing")
+                }
+                Some("data-not-unique")
+                | Some("too-many-elements")
+                | Some("too-few-elements")
+                | Some("must-violation") => ("application", "operation-failed"),
+                _ => ("application", "invalid-value"),
+            },
+            ErrorKind::Denied => ("application", "access-denied"),
+            ErrorKind::OutOfMemory => ("application", "resource-denied"),
+            _ => ("application", "operation-failed"),
+        }
+    }
 }
 
 impl std::fmt::Display for Error {
-    // Print only the base error message by default.
+    // Print the base error message followed by the path and application tag
+    // of the error, if any.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if let Some(msg) = &self.msg {
-            write!(f, "{}", msg)
+            write!(f, "{}", msg)?;
         } else {
-            write!(f, "Unknown error: {}", self.errcode)
+            write!(f, "Unknown error: {}", self.errcode)?;
         }
+        if let Some(path) = &self.path {
+            write!(f, " (path: {})", path)?;
+        }
+        if let Some(apptag) = &self.apptag {
+            write!(f, " (app-tag: {})", apptag)?;
+        }
+
+        Ok(())
     }
 }
 
@@ -7,8 +7,8 @@
 //! YANG iterators.
 
 use crate::context::Context;
-use crate::data::Metadata;
-use crate::schema::SchemaModule;
+use crate::data::{Attribute, DataNodeRef, Metadata};
+use crate::schema::{DataValue, SchemaModule};
 use crate::utils::Binding;
 use libyang2_sys as ffi;
 
@@ -59,6 +59,20 @@ where
     _marker: std::marker::PhantomData<&'a T>,
 }
 
+/// An iterator over all elements in a tree up to a maximum depth
+/// (depth-first search algorithm). Each element is yielded along with its
+/// depth relative to the starting element, which has depth zero.
+#[derive(Debug)]
+pub struct TraverseDepth<'a, T>
+where
+    T: NodeIterable<'a>,
+{
+    start: T,
+    next: Option<(T, usize)>,
+    max_depth: usize,
+    _marker: std::marker::PhantomData<&'a T>,
+}
+
 /// An iterator over a set of nodes.
 ///
 /// This is a safe wrapper around ffi::ly_set.
@@ -96,6 +110,12 @@ pub struct MetadataList<'a> {
     next: Option<Metadata<'a>>,
 }
 
+/// An iterator over a list of opaque attributes.
+#[derive(Debug)]
+pub struct Attributes<'a> {
+    next: Option<Attribute<'a>>,
+}
+
 // ===== impl Siblings =====
 
 impl<'a, T> Siblings<'a, T>
@@ -213,6 +233,74 @@ where
     }
 }
 
+// ===== impl TraverseDepth =====
+
+impl<'a, T> TraverseDepth<'a, T>
+where
+    T: NodeIterable<'a>,
+{
+    pub fn new(start: T, max_depth: usize) -> TraverseDepth<'a, T> {
+        let next = start.clone();
+
+        TraverseDepth {
+            start,
+            next: Some((next, 0)),
+            max_depth,
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<'a, T> Iterator for TraverseDepth<'a, T>
+where
+    T: NodeIterable<'a>,
+{
+    type Item = (T, usize);
+
+    fn next(&mut self) -> Option<(T, usize)> {
+        let ret = self.next.clone();
+
+        if let Some((elem, depth)) = &mut self.next {
+            // Select element for the next run - children first, unless the
+            // maximum depth was reached.
+            let mut next_elem = if *depth < self.max_depth {
+                elem.first_child()
+            } else {
+                None
+            };
+            if next_elem.is_some() {
+                *depth += 1;
+            } else {
+                // Check end condition.
+                if *elem == self.start {
+                    self.next = None;
+                    return ret;
+                }
+
+                // No children, try siblings.
+                next_elem = elem.next_sibling();
+            }
+
+            while next_elem.is_none() {
+                // Parent is already processed, go to its sibling.
+                *elem = elem.parent().unwrap();
+                *depth -= 1;
+
+                // Check end condition.
+                if *elem == self.start {
+                    self.next = None;
+                    return ret;
+                }
+                next_elem = elem.next_sibling();
+            }
+
+            *elem = next_elem.unwrap();
+        }
+
+        ret
+    }
+}
+
 // ===== impl Set =====
 
 impl<'a, T> Set<'a, T>
@@ -249,6 +337,21 @@ where
     }
 }
 
+impl<'a> Set<'a, DataNodeRef<'a>> {
+    /// Returns an iterator over the values of the data nodes in the set
+    /// (canonical string representation). `None` is yielded for nodes without
+    /// a value.
+    pub fn values(self) -> impl Iterator<Item = Option<String>> + 'a {
+        self.map(|dnode| dnode.value_canonical())
+    }
+
+    /// Returns an iterator over the values of the data nodes in the set
+    /// (typed representation). Nodes without a value are skipped.
+    pub fn typed_values(self) -> impl Iterator<Item = DataValue> + 'a {
+        self.filter_map(|dnode| dnode.value())
+    }
+}
+
 unsafe impl<'a, T> Send for Set<'a, T> where T: NodeIterable<'a> {}
 unsafe impl<'a, T> Sync for Set<'a, T> where T: NodeIterable<'a> {}
 
@@ -348,3 +451,23 @@ impl<'a> Iterator for MetadataList<'a> {
         meta
     }
 }
+
+// ===== impl Attributes =====
+
+impl Attributes<'_> {
+    pub fn new(next: Option<Attribute<'_>>) -> Attributes<'_> {
+        Attributes { next }
+    }
+}
+
+impl<'a> Iterator for Attributes<'a> {
+    type Item = Attribute<'a>;
+
+    fn next(&mut self) -> Option<Attribute<'a>> {
+        let attr = self.next.clone();
+        if let Some(next) = &self.next {
+            self.next = next.next();
+        }
+        attr
+    }
+}
@@ -33,6 +33,10 @@
 //!   libyang2 versions.
 //!   * Additional build requirements: *bindgen 0.55.0*
 //!
+//! The following optional features are also available:
+//! * **serde_json**: conversion of data trees from/to [`serde_json::Value`]
+//!   (see `DataTree::from_json_value` and `DataTree::to_json_value`).
+//!
 //! ## Examples
 //!
 //! See <https://github.com/rwestphal/yang2-rs/tree/master/examples>
@@ -47,7 +51,7 @@ pub mod iter;
 pub mod schema;
 pub mod utils;
 
-pub use crate::error::Error;
+pub use crate::error::{Error, ErrorKind};
 
 // Re-export the raw FFI bindings for convenience.
 pub use libyang2_sys as ffi;
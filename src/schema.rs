@@ -10,12 +10,13 @@ use bitflags::bitflags;
 use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
 use std::ffi::CString;
+use std::fmt;
 use std::mem;
 use std::os::raw::{c_char, c_void};
 use std::os::unix::io::AsRawFd;
 use std::slice;
 
-use crate::context::Context;
+use crate::context::{Context, ContextFlags};
 use crate::error::{Error, Result};
 use crate::iter::{Ancestors, Array, NodeIterable, Set, Siblings, Traverse};
 use crate::utils::*;
@@ -25,7 +26,7 @@ use libyang2_sys as ffi;
 #[derive(Clone, Debug)]
 pub struct SchemaModule<'a> {
     context: &'a Context,
-    raw: *mut ffi::lys_module,
+    pub(crate) raw: *mut ffi::lys_module,
 }
 
 /// Schema input formats accepted by libyang.
@@ -88,6 +89,7 @@ pub enum SchemaNodeKind {
     LeafList,
     List,
     AnyData,
+    AnyXml,
     Rpc,
     Input,
     Output,
@@ -109,6 +111,31 @@ pub struct SchemaStmtWhen<'a> {
     _marker: std::marker::PhantomData<&'a Context>,
 }
 
+/// YANG extension instance.
+#[derive(Clone, Debug)]
+pub struct SchemaExtInstance<'a> {
+    context: &'a Context,
+    raw: *mut ffi::lysc_ext_instance,
+}
+
+/// YANG identity.
+#[derive(Clone, Debug)]
+pub struct SchemaIdentity<'a> {
+    context: &'a Context,
+    raw: *mut ffi::lysc_ident,
+}
+
+/// YANG leaf(-list) type.
+#[derive(Clone, Debug)]
+pub struct SchemaLeafType<'a> {
+    raw: *mut ffi::lysc_type,
+    // Parsed type and the parsed node where it's used. Only available when
+    // the context was created with the SET_PRIV_PARSED flag.
+    rparsed: *const ffi::lysp_type,
+    rscope: *const ffi::lysp_node,
+    _marker: std::marker::PhantomData<&'a Context>,
+}
+
 /// YANG data value type.
 #[derive(Copy, Clone, Debug, PartialEq, FromPrimitive)]
 pub enum DataValueType {
@@ -154,7 +181,10 @@ pub enum DataValue {
 
 impl<'a> SchemaModule<'a> {
     /// Name of the module.
-    pub fn name(&self) -> &str {
+    ///
+    /// The name is interned in the context dictionary, so it's borrowed for
+    /// the lifetime of the context rather than of this module reference.
+    pub fn name(&self) -> &'a str {
         char_ptr_to_str(unsafe { (*self.raw).name })
     }
 
@@ -164,12 +194,12 @@ impl<'a> SchemaModule<'a> {
     }
 
     /// Namespace of the module.
-    pub fn namespace(&self) -> &str {
+    pub fn namespace(&self) -> &'a str {
         char_ptr_to_str(unsafe { (*self.raw).ns })
     }
 
     /// Prefix of the module.
-    pub fn prefix(&self) -> &str {
+    pub fn prefix(&self) -> &'a str {
         char_ptr_to_str(unsafe { (*self.raw).prefix })
     }
 
@@ -271,6 +301,60 @@ impl<'a> SchemaModule<'a> {
         Ok(char_ptr_to_string(cstr))
     }
 
+    /// Returns the modules imported by this module or by any of its
+    /// submodules.
+    ///
+    /// Requires the parsed module, which is always available unless the
+    /// parsed modules of the context were freed.
+    pub fn imports(&self) -> impl Iterator<Item = SchemaModule<'a>> {
+        let mut imports = Vec::new();
+        let pmod = unsafe { (*self.raw).parsed };
+        if !pmod.is_null() {
+            let rimports = std::iter::once(unsafe { (*pmod).imports }).chain(
+                self.parsed_includes()
+                    .iter()
+                    .filter(|include| !include.submodule.is_null())
+                    .map(|include| unsafe { (*include.submodule).imports }),
+            );
+            for rimports in rimports {
+                for import in unsafe { sized_array(rimports) } {
+                    let module = unsafe {
+                        SchemaModule::from_raw(self.context, import.module)
+                    };
+                    if !imports.contains(&module) {
+                        imports.push(module);
+                    }
+                }
+            }
+        }
+        imports.into_iter()
+    }
+
+    /// Returns the names of the submodules included by this module.
+    pub fn includes(&self) -> impl Iterator<Item = &'a str> {
+        self.parsed_includes()
+            .iter()
+            .map(|include| char_ptr_to_str(include.name))
+    }
+
+    /// Returns the modules that apply deviations to this module. When
+    /// troubleshooting unexpected behavior, this can be used to find out
+    /// whether the schema of this module was altered by another one.
+    pub fn deviated_by(&self) -> Vec<SchemaModule<'a>> {
+        let rdeviated_by = unsafe { (*self.raw).deviated_by };
+        unsafe { sized_array(rdeviated_by) }
+            .iter()
+            .map(|rmodule| unsafe {
+                SchemaModule::from_raw(self.context, *rmodule)
+            })
+            .collect()
+    }
+
+    /// Returns whether this module is deviated by at least one other module.
+    pub fn is_deviated(&self) -> bool {
+        !self.deviated_by().is_empty()
+    }
+
     /// Returns an iterator over the top-level data nodes.
     pub fn data(&self) -> Siblings<'a, SchemaNode<'a>> {
         let compiled = unsafe { (*self.raw).compiled };
@@ -322,6 +406,15 @@ impl<'a> SchemaModule<'a> {
             self.notifications().flat_map(|snode| snode.traverse());
         data.chain(rpcs).chain(notifications)
     }
+
+    // Returns the include statements of the parsed module.
+    fn parsed_includes(&self) -> &'a [ffi::lysp_include] {
+        let pmod = unsafe { (*self.raw).parsed };
+        if pmod.is_null() {
+            return &[];
+        }
+        unsafe { sized_array((*pmod).includes) }
+    }
 }
 
 unsafe impl<'a> Binding<'a> for SchemaModule<'a> {
@@ -355,7 +448,7 @@ impl<'a> SchemaNode<'a> {
     }
 
     /// Schema node module.
-    pub fn module(&self) -> SchemaModule<'_> {
+    pub fn module(&self) -> SchemaModule<'a> {
         let module = unsafe { (*self.raw).module };
         unsafe { SchemaModule::from_raw(self.context, module) }
     }
@@ -366,7 +459,11 @@ impl<'a> SchemaNode<'a> {
     }
 
     /// Schema node name.
-    pub fn name(&self) -> &str {
+    ///
+    /// The name is interned in the context dictionary, so it's borrowed for
+    /// the lifetime of the context rather than of this node reference. This
+    /// allows to compare the names of many nodes without any allocation.
+    pub fn name(&self) -> &'a str {
         char_ptr_to_str(unsafe { (*self.raw).name })
     }
 
@@ -449,7 +546,8 @@ impl<'a> SchemaNode<'a> {
             | SchemaNodeKind::Leaf
             | SchemaNodeKind::LeafList
             | SchemaNodeKind::List
-            | SchemaNodeKind::AnyData => self.check_flag(ffi::LYS_CONFIG_W),
+            | SchemaNodeKind::AnyData
+            | SchemaNodeKind::AnyXml => self.check_flag(ffi::LYS_CONFIG_W),
             _ => false,
         }
     }
@@ -463,7 +561,8 @@ impl<'a> SchemaNode<'a> {
             | SchemaNodeKind::Leaf
             | SchemaNodeKind::LeafList
             | SchemaNodeKind::List
-            | SchemaNodeKind::AnyData => self.check_flag(ffi::LYS_CONFIG_R),
+            | SchemaNodeKind::AnyData
+            | SchemaNodeKind::AnyXml => self.check_flag(ffi::LYS_CONFIG_R),
             _ => false,
         }
     }
@@ -491,7 +590,8 @@ impl<'a> SchemaNode<'a> {
             | SchemaNodeKind::Leaf
             | SchemaNodeKind::LeafList
             | SchemaNodeKind::List
-            | SchemaNodeKind::AnyData => self.check_flag(ffi::LYS_MAND_TRUE),
+            | SchemaNodeKind::AnyData
+            | SchemaNodeKind::AnyXml => self.check_flag(ffi::LYS_MAND_TRUE),
             _ => false,
         }
     }
@@ -520,7 +620,9 @@ impl<'a> SchemaNode<'a> {
         }
     }
 
-    /// Returns whether the node is an user-ordered list or leaf-list.
+    /// Returns whether the node is an user-ordered list or leaf-list. The
+    /// relative order of the instances of such nodes is significant, unlike
+    /// for system-ordered ones.
     pub fn is_user_ordered(&self) -> bool {
         match self.kind {
             SchemaNodeKind::LeafList | SchemaNodeKind::List => {
@@ -545,7 +647,8 @@ impl<'a> SchemaNode<'a> {
             | SchemaNodeKind::Leaf
             | SchemaNodeKind::LeafList
             | SchemaNodeKind::List
-            | SchemaNodeKind::AnyData => self.check_flag(ffi::LYS_IS_INPUT),
+            | SchemaNodeKind::AnyData
+            | SchemaNodeKind::AnyXml => self.check_flag(ffi::LYS_IS_INPUT),
             _ => false,
         }
     }
@@ -559,7 +662,8 @@ impl<'a> SchemaNode<'a> {
             | SchemaNodeKind::Leaf
             | SchemaNodeKind::LeafList
             | SchemaNodeKind::List
-            | SchemaNodeKind::AnyData => self.check_flag(ffi::LYS_IS_OUTPUT),
+            | SchemaNodeKind::AnyData
+            | SchemaNodeKind::AnyXml => self.check_flag(ffi::LYS_IS_OUTPUT),
             _ => false,
         }
     }
@@ -573,7 +677,8 @@ impl<'a> SchemaNode<'a> {
             | SchemaNodeKind::Leaf
             | SchemaNodeKind::LeafList
             | SchemaNodeKind::List
-            | SchemaNodeKind::AnyData => self.check_flag(ffi::LYS_IS_NOTIF),
+            | SchemaNodeKind::AnyData
+            | SchemaNodeKind::AnyXml => self.check_flag(ffi::LYS_IS_NOTIF),
             _ => false,
         }
     }
@@ -595,6 +700,9 @@ impl<'a> SchemaNode<'a> {
                 SchemaNodeKind::Leaf => {
                     let rvalue =
                         (*(self.raw as *const ffi::lysc_node_leaf)).dflt;
+                    if rvalue.is_null() {
+                        return None;
+                    }
                     let mut canonical = (*rvalue)._canonical;
                     if canonical.is_null() {
                         canonical = ffi::lyd_value_get_canonical(
@@ -662,6 +770,78 @@ impl<'a> SchemaNode<'a> {
         Some(DataValueType::from_u32(ltype).unwrap())
     }
 
+    /// Type of the leaf(-list).
+    pub fn leaf_type(&self) -> Option<SchemaLeafType<'a>> {
+        let pnode = self.parsed();
+        let (raw, rparsed) = unsafe {
+            match self.kind() {
+                SchemaNodeKind::Leaf => {
+                    let raw = (*(self.raw as *mut ffi::lysc_node_leaf)).type_;
+                    let rparsed = if pnode.is_null() {
+                        std::ptr::null()
+                    } else {
+                        &(*(pnode as *const ffi::lysp_node_leaf)).type_
+                            as *const _
+                    };
+                    (raw, rparsed)
+                }
+                SchemaNodeKind::LeafList => {
+                    let raw =
+                        (*(self.raw as *mut ffi::lysc_node_leaflist)).type_;
+                    let rparsed = if pnode.is_null() {
+                        std::ptr::null()
+                    } else {
+                        &(*(pnode as *const ffi::lysp_node_leaflist)).type_
+                            as *const _
+                    };
+                    (raw, rparsed)
+                }
+                _ => return None,
+            }
+        };
+        let rscope = if pnode.is_null() {
+            std::ptr::null()
+        } else {
+            unsafe { (*pnode).parent as *const _ }
+        };
+
+        Some(SchemaLeafType {
+            raw,
+            rparsed,
+            rscope,
+            _marker: std::marker::PhantomData,
+        })
+    }
+
+    /// Check whether the given value is valid for the type of the leaf(-list),
+    /// without creating any data node.
+    ///
+    /// Restrictions that depend on data, like leafref targets or
+    /// instance-identifiers, can't be checked and are assumed to be
+    /// satisfied. On failure, the returned error carries the type-specific
+    /// message reported by libyang.
+    pub fn validate_value(&self, value: &str) -> Result<()> {
+        self.value_validate(value, std::ptr::null_mut())
+    }
+
+    /// Get the canonical form of the given value for the type of the
+    /// leaf(-list) (e.g. "0x1F" becomes "31" for integer types).
+    ///
+    /// The value is validated the same way as in
+    /// [`SchemaNode::validate_value`], and an error is returned if it isn't
+    /// valid.
+    pub fn canonicalize_value(&self, value: &str) -> Result<String> {
+        let mut canonical = std::ptr::null();
+        self.value_validate(value, &mut canonical)?;
+
+        if canonical.is_null() {
+            return Ok(value.to_owned());
+        }
+        let ret = char_ptr_to_string(canonical);
+        unsafe { ffi::lydict_remove(self.context.raw, canonical) };
+        Ok(ret)
+    }
+
     /// Units of the leaf(-list)'s type.
     pub fn units(&self) -> Option<&str> {
         let units = unsafe {
@@ -734,8 +914,61 @@ impl<'a> SchemaNode<'a> {
         Array::new(self.context, array as *mut _, ptr_size)
     }
 
+    /// Schema nodes referenced by the node's when statements, including the
+    /// ones of the when statements inherited from uses and augments.
+    ///
+    /// The XPath conditions are atomized, so the schema nodes traversed to
+    /// reach the referenced ones (e.g. parent nodes) are also returned. The
+    /// node itself is never returned.
+    pub fn when_dependencies(&self) -> Result<Vec<SchemaNode<'a>>> {
+        let mut options = 0u32;
+        if self.check_flag(ffi::LYS_IS_OUTPUT) {
+            options |= ffi::LYS_FIND_XP_OUTPUT;
+        }
+
+        let mut dependencies: Vec<SchemaNode<'a>> = Vec::new();
+        for when in self.whens() {
+            let mut set = std::ptr::null_mut();
+            let set_ptr = &mut set;
+
+            let ret = unsafe {
+                ffi::lys_find_expr_atoms(
+                    (*when.raw).context,
+                    (*self.raw).module,
+                    (*when.raw).cond,
+                    (*when.raw).prefixes,
+                    options,
+                    set_ptr,
+                )
+            };
+            if ret != ffi::LY_ERR::LY_SUCCESS {
+                return Err(Error::new(self.context));
+            }
+
+            let rnodes_count = unsafe { (*set).count } as usize;
+            for i in 0..rnodes_count {
+                let rnode = unsafe { *(*set).__bindgen_anon_1.snodes.add(i) };
+                let snode =
+                    unsafe { SchemaNode::from_raw(self.context, rnode) };
+                if snode != *self && !dependencies.contains(&snode) {
+                    dependencies.push(snode);
+                }
+            }
+            unsafe { ffi::ly_set_free(set, None) };
+        }
+
+        Ok(dependencies)
+    }
+
+    /// Array of extension instances.
+    pub fn extensions(&self) -> Array<'_, SchemaExtInstance<'_>> {
+        let array = unsafe { (*self.raw).exts };
+        let ptr_size = mem::size_of::<ffi::lysc_ext_instance>();
+        Array::new(self.context, array, ptr_size)
+    }
+
     /// Array of actions.
-    pub fn actions(&self) -> Option<Array<'_, SchemaNode<'_>>> {
+    pub fn actions(&self) -> Option<Array<'a, SchemaNode<'a>>> {
         let array = unsafe {
             match self.kind {
                 SchemaNodeKind::Container => {
@@ -753,7 +986,7 @@ impl<'a> SchemaNode<'a> {
     }
 
     /// Array of notifications.
-    pub fn notifications(&self) -> Option<Array<'_, SchemaNode<'_>>> {
+    pub fn notifications(&self) -> Option<Array<'a, SchemaNode<'a>>> {
         let array = unsafe {
             match self.kind {
                 SchemaNodeKind::Container => {
@@ -863,13 +1096,23 @@ impl<'a> SchemaNode<'a> {
     ///
     /// # Safety
     ///
-    /// The caller must ensure that the provided pointer is valid.
+    /// The caller must ensure that the provided pointer is valid. The private
+    /// pointer must not be set when the context was created with the
+    /// [`ContextFlags::SET_PRIV_PARSED`] flag, since libyang uses it to store
+    /// the corresponding parsed node.
     pub unsafe fn set_private(&self, ptr: *mut c_void) {
         (*self.raw).priv_ = ptr;
     }
 
     /// Get private user data, not used by libyang.
+    ///
+    /// Always returns `None` when the context was created with the
+    /// [`ContextFlags::SET_PRIV_PARSED`] flag.
     pub fn get_private(&self) -> Option<*mut c_void> {
+        if self.has_parsed() {
+            return None;
+        }
+
         let priv_ = unsafe { (*self.raw).priv_ };
         if priv_.is_null() {
             None
@@ -877,6 +1120,48 @@ impl<'a> SchemaNode<'a> {
             Some(priv_)
         }
     }
+
+    // Validate the given value against the type of the leaf(-list), optionally
+    // returning its canonical form (stored in the context dictionary).
+    fn value_validate(
+        &self,
+        value: &str,
+        canonical: *mut *const c_char,
+    ) -> Result<()> {
+        let value_len = value.len();
+        let value = CString::new(value).unwrap();
+        let ret = unsafe {
+            ffi::lyd_value_validate(
+                self.context.raw,
+                self.raw,
+                value.as_ptr(),
+                value_len,
+                std::ptr::null(),
+                std::ptr::null_mut(),
+                canonical,
+            )
+        };
+        match ret {
+            ffi::LY_ERR::LY_SUCCESS | ffi::LY_ERR::LY_EINCOMPLETE => Ok(()),
+            _ => Err(Error::new(self.context)),
+        }
+    }
+
+    // Returns the parsed node corresponding to this compiled node, or a null
+    // pointer if the context doesn't keep track of it.
+    fn parsed(&self) -> *const ffi::lysp_node {
+        if !self.has_parsed() {
+            return std::ptr::null();
+        }
+        unsafe { (*self.raw).priv_ as *const _ }
+    }
+
+    // Check whether the private pointer refers to the parsed node.
+    fn has_parsed(&self) -> bool {
+        self.context
+            .get_options()
+            .contains(ContextFlags::SET_PRIV_PARSED)
+    }
 }
 
 unsafe impl<'a> Binding<'a> for SchemaNode<'a> {
@@ -896,6 +1181,7 @@ unsafe impl<'a> Binding<'a> for SchemaNode<'a> {
             ffi::LYS_LEAFLIST => SchemaNodeKind::LeafList,
             ffi::LYS_LIST => SchemaNodeKind::List,
             ffi::LYS_ANYDATA => SchemaNodeKind::AnyData,
+            ffi::LYS_ANYXML => SchemaNodeKind::AnyXml,
             ffi::LYS_ACTION => SchemaNodeKind::Action,
             ffi::LYS_RPC => SchemaNodeKind::Rpc,
             ffi::LYS_INPUT => SchemaNodeKind::Input,
@@ -980,7 +1266,10 @@ unsafe impl Sync for SchemaStmtMust<'_> {}
 // ===== impl SchemaStmtWhen =====
 
 impl<'a> SchemaStmtWhen<'a> {
-    // TODO: XPath condition
+    /// XPath condition.
+    pub fn condition(&self) -> &str {
+        char_ptr_to_str(unsafe { ffi::lyxp_get_expr((*self.raw).cond) })
+    }
 
     /// description substatement.
     pub fn description(&self) -> Option<&str> {
@@ -1012,6 +1301,160 @@ unsafe impl<'a> Binding<'a> for SchemaStmtWhen<'a> {
 unsafe impl Send for SchemaStmtWhen<'_> {}
 unsafe impl Sync for SchemaStmtWhen<'_> {}
 
+// ===== impl SchemaExtInstance =====
+
+impl<'a> SchemaExtInstance<'a> {
+    /// Name of the extension.
+    pub fn name(&self) -> &str {
+        char_ptr_to_str(unsafe { (*(*self.raw).def).name })
+    }
+
+    /// Module where the extension is defined.
+    pub fn module(&self) -> SchemaModule<'_> {
+        let module = unsafe { (*(*self.raw).def).module };
+        unsafe { SchemaModule::from_raw(self.context, module) }
+    }
+
+    /// Argument of the extension instance, if any.
+    pub fn argument(&self) -> Option<&str> {
+        char_ptr_to_opt_str(unsafe { (*self.raw).argument })
+    }
+}
+
+unsafe impl<'a> Binding<'a> for SchemaExtInstance<'a> {
+    type CType = ffi::lysc_ext_instance;
+    type Container = Context;
+
+    unsafe fn from_raw(
+        context: &'a Context,
+        raw: *mut ffi::lysc_ext_instance,
+    ) -> SchemaExtInstance<'a> {
+        SchemaExtInstance { context, raw }
+    }
+}
+
+unsafe impl Send for SchemaExtInstance<'_> {}
+unsafe impl Sync for SchemaExtInstance<'_> {}
+
+// ===== impl SchemaIdentity =====
+
+impl<'a> SchemaIdentity<'a> {
+    /// Name of the identity.
+    pub fn name(&self) -> &'a str {
+        char_ptr_to_str(unsafe { (*self.raw).name })
+    }
+
+    /// Module where the identity is defined.
+    pub fn module(&self) -> SchemaModule<'a> {
+        let module = unsafe { (*self.raw).module };
+        unsafe { SchemaModule::from_raw(self.context, module) }
+    }
+
+    /// description substatement.
+    pub fn description(&self) -> Option<&str> {
+        char_ptr_to_opt_str(unsafe { (*self.raw).dsc })
+    }
+
+    /// reference substatement.
+    pub fn reference(&self) -> Option<&str> {
+        char_ptr_to_opt_str(unsafe { (*self.raw).ref_ })
+    }
+}
+
+unsafe impl<'a> Binding<'a> for SchemaIdentity<'a> {
+    type CType = ffi::lysc_ident;
+    type Container = Context;
+
+    unsafe fn from_raw(
+        context: &'a Context,
+        raw: *mut ffi::lysc_ident,
+    ) -> SchemaIdentity<'a> {
+        SchemaIdentity { context, raw }
+    }
+}
+
+impl<'a> PartialEq for SchemaIdentity<'a> {
+    fn eq(&self, other: &SchemaIdentity<'_>) -> bool {
+        self.raw == other.raw
+    }
+}
+
+impl fmt::Display for SchemaIdentity<'_> {
+    // Print the identity qualified by the name of its module.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.module().name(), self.name())
+    }
+}
+
+unsafe impl Send for SchemaIdentity<'_> {}
+unsafe impl Sync for SchemaIdentity<'_> {}
+
+// ===== impl SchemaLeafType =====
+
+impl<'a> SchemaLeafType<'a> {
+    /// Resolved base type.
+    pub fn base_type(&self) -> DataValueType {
+        let ltype = unsafe { (*self.raw).basetype };
+        DataValueType::from_u32(ltype).unwrap()
+    }
+
+    /// Name of the type as written in the schema (e.g. "inet:ipv4-address").
+    ///
+    /// Compiled schemas don't retain type names, so this requires the
+    /// [`ContextFlags::SET_PRIV_PARSED`] flag to be set in the context (either
+    /// when it's created or later using [`Context::set_options`]). Otherwise
+    /// `None` is returned.
+    pub fn type_name(&self) -> Option<&str> {
+        if self.rparsed.is_null() {
+            return None;
+        }
+        Some(char_ptr_to_str(unsafe { (*self.rparsed).name }))
+    }
+
+    /// Names of the types this type is derived from, following the typedef
+    /// chain from the nearest typedef down to the built-in type.
+    ///
+    /// For example, a leaf of type "inet:port-number" yields `["uint16"]`.
+    /// The list is empty when the type is a built-in one or when the parsed
+    /// schema isn't available (see [`SchemaLeafType::type_name`]).
+    pub fn derived_from(&self) -> Vec<&str> {
+        let mut chain = Vec::new();
+        if self.rparsed.is_null() {
+            return chain;
+        }
+
+        let mut rtype = self.rparsed;
+        let mut rscope = self.rscope;
+        while let Some((tpdf, tscope)) = unsafe { find_typedef(rtype, rscope) }
+        {
+            rtype = unsafe { &(*tpdf).type_ };
+            rscope = tscope;
+            chain.push(char_ptr_to_str(unsafe { (*rtype).name }));
+        }
+        chain
+    }
+}
+
+unsafe impl<'a> Binding<'a> for SchemaLeafType<'a> {
+    type CType = ffi::lysc_type;
+    type Container = Context;
+
+    unsafe fn from_raw(
+        _context: &'a Context,
+        raw: *mut ffi::lysc_type,
+    ) -> SchemaLeafType<'a> {
+        SchemaLeafType {
+            raw,
+            rparsed: std::ptr::null(),
+            rscope: std::ptr::null(),
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+unsafe impl Send for SchemaLeafType<'_> {}
+unsafe impl Sync for SchemaLeafType<'_> {}
+
 // ===== impl DataValue =====
 
 impl DataValue {
@@ -1067,3 +1510,120 @@ impl DataValue {
         }
     }
 }
+
+impl fmt::Display for DataValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataValue::Uint8(value) => write!(f, "{}", value),
+            DataValue::Uint16(value) => write!(f, "{}", value),
+            DataValue::Uint32(value) => write!(f, "{}", value),
+            DataValue::Uint64(value) => write!(f, "{}", value),
+            DataValue::Bool(value) => write!(f, "{}", value),
+            DataValue::Empty => Ok(()),
+            DataValue::Int8(value) => write!(f, "{}", value),
+            DataValue::Int16(value) => write!(f, "{}", value),
+            DataValue::Int32(value) => write!(f, "{}", value),
+            DataValue::Int64(value) => write!(f, "{}", value),
+            DataValue::Other(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+// ===== helper functions =====
+
+const BUILTIN_TYPES: [&str; 19] = [
+    "binary",
+    "bits",
+    "boolean",
+    "decimal64",
+    "empty",
+    "enumeration",
+    "identityref",
+    "instance-identifier",
+    "int8",
+    "int16",
+    "int32",
+    "int64",
+    "leafref",
+    "string",
+    "uint8",
+    "uint16",
+    "uint32",
+    "uint64",
+    "union",
+];
+
+// Looks up the typedef of the given parsed type, returning it together with
+// the scope (parsed node) where it was defined. Returns `None` for built-in
+// types or if the typedef can't be found.
+unsafe fn find_typedef(
+    rtype: *const ffi::lysp_type,
+    mut rscope: *const ffi::lysp_node,
+) -> Option<(*const ffi::lysp_tpdf, *const ffi::lysp_node)> {
+    let pmod = (*rtype).pmod;
+    if pmod.is_null() {
+        return None;
+    }
+
+    let name = char_ptr_to_str((*rtype).name);
+    let (prefix, name) = match name.split_once(':') {
+        Some((prefix, name)) => (Some(prefix), name),
+        None if BUILTIN_TYPES.contains(&name) => return None,
+        None => (None, name),
+    };
+
+    // Typedef from an imported module. Prefixes not matching any import
+    // refer to the local module.
+    if let Some(prefix) = prefix {
+        if let Some(import) = sized_array((*pmod).imports)
+            .iter()
+            .find(|import| char_ptr_to_str(import.prefix) == prefix)
+        {
+            let tpdf = find_module_typedef((*import.module).parsed, name)?;
+            return Some((tpdf, std::ptr::null()));
+        }
+    }
+
+    // Scoped typedef.
+    while !rscope.is_null() {
+        if let Some(tpdf) = sized_array(ffi::lysp_node_typedefs(rscope))
+            .iter()
+            .find(|tpdf| char_ptr_to_str(tpdf.name) == name)
+        {
+            return Some((tpdf, rscope));
+        }
+        rscope = (*rscope).parent;
+    }
+
+    // Top-level typedef from the local module or one of its submodules.
+    let tpdf = find_module_typedef(pmod, name)
+        .or_else(|| find_module_typedef((*(*pmod).mod_).parsed, name))?;
+    Some((tpdf, std::ptr::null()))
+}
+
+// Looks up a top-level typedef in the given parsed (sub)module and in the
+// submodules it includes.
+unsafe fn find_module_typedef(
+    pmod: *const ffi::lysp_module,
+    name: &str,
+) -> Option<*const ffi::lysp_tpdf> {
+    if pmod.is_null() {
+        return None;
+    }
+
+    let typedefs = std::iter::once((*pmod).typedefs).chain(
+        sized_array((*pmod).includes)
+            .iter()
+            .filter(|include| !include.submodule.is_null())
+            .map(|include| (*include.submodule).typedefs),
+    );
+    for typedefs in typedefs {
+        if let Some(tpdf) = sized_array(typedefs)
+            .iter()
+            .find(|tpdf| char_ptr_to_str(tpdf.name) == name)
+        {
+            return Some(tpdf);
+        }
+    }
+    None
+}
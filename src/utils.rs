@@ -6,6 +6,10 @@
 
 use std::ffi::CStr;
 use std::os::raw::c_char;
+use std::slice;
+
+use crate::error::{Error, Result};
+use libyang2_sys as ffi;
 
 /// Convert C String to string slice.
 pub(crate) fn char_ptr_to_string(c_str: *const c_char) -> String {
@@ -35,6 +39,43 @@ pub(crate) fn char_ptr_to_opt_str<'a>(c_str: *const c_char) -> Option<&'a str> {
     }
 }
 
+/// Returns a slice over a libyang2 sized array.
+pub(crate) unsafe fn sized_array<'a, T>(raw: *const T) -> &'a [T] {
+    if raw.is_null() {
+        return &[];
+    }
+    let count = (raw as *const usize).offset(-1).read();
+    slice::from_raw_parts(raw, count)
+}
+
+/// Check whether an absolute path or XPath expression is in the JSON format,
+/// meaning its first node must have its module name as prefix or be the
+/// special `*` value. Relative expressions are not checked.
+pub(crate) fn check_json_xpath(xpath: &str) -> Result<()> {
+    if !xpath.starts_with('/') {
+        return Ok(());
+    }
+
+    let step = xpath
+        .trim_start_matches('/')
+        .split(|c: char| "/[|() ".contains(c))
+        .next()
+        .unwrap_or_default();
+    if step.is_empty() || step == "*" || step.contains(':') {
+        return Ok(());
+    }
+
+    Err(Error::with_msg(
+        ffi::LY_ERR::LY_EINVAL,
+        format!(
+            "Invalid expression \"{}\": the first node \"{}\" must be \
+             prefixed by its module name (JSON format)",
+            xpath, step
+        ),
+        None,
+    ))
+}
+
 /// A trait implemented by all types that can be created from a raw C pointer
 /// and a generic container type.
 pub unsafe trait Binding<'a>
@@ -1,11 +1,19 @@
 use std::collections::BTreeSet;
+use std::ffi::CString;
 use std::sync::Arc;
-use yang2::context::{Context, ContextFlags};
+use yang2::context::{
+    Context, ContextFlags, EmbeddedModuleKey, EmbeddedModules,
+};
 use yang2::data::{
-    Data, DataDiff, DataDiffFlags, DataFormat, DataImplicitFlags,
-    DataOperation, DataParserFlags, DataPrinterFlags, DataTree,
-    DataValidationFlags,
+    ByContent, Data, DataAnyValue, DataCompareFlags, DataDiff, DataDiffFlags,
+    DataDiffOp, DataFormat, DataImplicitFlags, DataNodeRef, DataNodeType,
+    DataOperation, DataParser, DataParserFlags, DataPrinterFlags,
+    DataStoreBuilder, DataTree, DataValidationFlags, DataVisitor, DiffSummary,
+    EditOperation, NewPathOutcome, ValueFormat, WalkEvent, YangPatch,
+    YangPatchEdit, YangPatchOperation, YangPatchWhere,
 };
+use yang2::schema::{DataValue, DataValueType, SchemaNodeKind};
+use yang2::{ffi, ErrorKind};
 
 static SEARCH_DIR: &str = "./assets/yang/";
 static JSON_TREE1: &str = r###"
@@ -191,6 +199,8 @@ fn create_context() -> Arc<Context> {
         "ietf-ip",
         "ietf-routing",
         "ietf-isis",
+        "ietf-yang-patch",
+        "yang2-test",
     ] {
         ctx.load_module(module_name, None, &[])
             .expect("Failed to load module");
@@ -275,19 +285,129 @@ fn data_find_xpath() {
     );
 }
 
+#[test]
+fn data_find_xpath_values() {
+    let ctx = create_context();
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+
+    assert_eq!(
+        dtree1
+            .find_xpath("/ietf-interfaces:interfaces/interface/description")
+            .expect("Failed to lookup data")
+            .values()
+            .collect::<Vec<_>>(),
+        vec![Some("ENG".to_owned()), Some("MKT".to_owned())]
+    );
+    assert_eq!(
+        dtree1
+            .find_xpath("/ietf-interfaces:interfaces/interface/enabled")
+            .expect("Failed to lookup data")
+            .typed_values()
+            .collect::<Vec<_>>(),
+        vec![DataValue::Bool(true), DataValue::Bool(true)]
+    );
+    assert_eq!(
+        dtree1
+            .find_xpath("/ietf-interfaces:interfaces/interface")
+            .expect("Failed to lookup data")
+            .values()
+            .collect::<Vec<_>>(),
+        vec![None, None]
+    );
+}
+
 #[test]
 fn data_find_path() {
     let ctx = create_context();
     let dtree1 = parse_json_data(&ctx, JSON_TREE1);
 
     assert!(dtree1
-        .find_path("/ietf-interfaces:interfaces/interface")
+        .find_path("/ietf-interfaces:interfaces/interface", false)
         .is_err());
     assert!(dtree1
-        .find_path("/ietf-interfaces:interfaces/interface[name='eth/0/0']")
+        .find_path(
+            "/ietf-interfaces:interfaces/interface[name='eth/0/0']",
+            false
+        )
         .is_ok());
 }
 
+#[test]
+fn data_find_path_output() {
+    let ctx = create_context();
+    let mut dtree = DataTree::new(&ctx);
+    dtree
+        .new_path("/yang2-test:echo/message", Some("hello"), true)
+        .expect("Failed to create data");
+
+    let dnode = dtree
+        .find_path("/yang2-test:echo/message", true)
+        .expect("Failed to lookup data");
+    assert_eq!(dnode.value_canonical().as_deref(), Some("hello"));
+    assert!(dtree.find_path("/yang2-test:echo/message", false).is_err());
+}
+
+#[test]
+fn data_parse_and_find() {
+    let ctx = create_context();
+
+    let dtree = DataTree::parse_and_find(
+        &ctx,
+        JSON_TREE1,
+        DataFormat::JSON,
+        "/ietf-interfaces:interfaces/interface[name='eth/0/0']/description",
+    )
+    .expect("Failed to parse and find data");
+    assert_eq!(
+        dtree.reference().and_then(|dnode| dnode.value_canonical()),
+        Some("ENG".to_owned())
+    );
+
+    let dtree = DataTree::parse_and_find(
+        &ctx,
+        JSON_TREE1,
+        DataFormat::JSON,
+        "/ietf-interfaces:interfaces/interface[name='eth/0/1']",
+    )
+    .expect("Failed to parse and find data");
+    assert_eq!(dtree.node_count(), 5);
+
+    assert!(DataTree::parse_and_find(
+        &ctx,
+        JSON_TREE1,
+        DataFormat::JSON,
+        "/ietf-interfaces:interfaces/interface[name='eth/0/9']",
+    )
+    .is_err());
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn data_json_value() {
+    let ctx = create_context();
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+
+    let value = dtree1.to_json_value().expect("Failed to convert data");
+    assert_eq!(
+        value["ietf-interfaces:interfaces"]["interface"][1]["description"],
+        "MKT"
+    );
+
+    let dtree2 = DataTree::from_json_value(
+        &ctx,
+        &value,
+        DataParserFlags::NO_VALIDATION,
+        DataValidationFlags::empty(),
+    )
+    .expect("Failed to parse data");
+    assert_data_eq!(&dtree1, &dtree2);
+
+    let value = DataTree::new(&ctx)
+        .to_json_value()
+        .expect("Failed to convert data");
+    assert_eq!(value, serde_json::json!({}));
+}
+
 #[test]
 fn data_edit() {
     let ctx = create_context();
@@ -336,6 +456,138 @@ fn data_edit() {
     assert_data_eq!(&dtree1, &dtree2);
 }
 
+#[test]
+fn data_remove_top_level() {
+    let ctx = create_context();
+    let mut dtree = parse_json_data(&ctx, JSON_TREE1);
+    dtree
+        .merge(&parse_json_data(
+            &ctx,
+            r###"{"yang2-test:misc": {"enabled": true}}"###,
+        ))
+        .expect("Failed to merge data trees");
+
+    // Remove the first top-level node, then its remaining sibling.
+    let path = dtree.reference().expect("Empty data tree").path();
+    dtree.remove(&path).expect("Failed to remove data");
+    assert!(dtree.find_path(&path, false).is_err());
+    assert!(dtree.node_count() > 0);
+    let path = dtree.reference().expect("Empty data tree").path();
+    assert!(dtree.find_path(&path, false).is_ok());
+    dtree.remove(&path).expect("Failed to remove data");
+    assert!(dtree.reference().is_none());
+    assert_eq!(dtree.node_count(), 0);
+}
+
+#[test]
+fn data_new_or_update_path() {
+    let ctx = create_context();
+    let mut dtree = parse_json_data(&ctx, JSON_TREE1);
+    let path = "/ietf-interfaces:interfaces/interface[name='eth/0/0']/enabled";
+
+    match dtree.new_or_update_path(path, Some("false"), false) {
+        Ok(NewPathOutcome::Updated(dnode)) => {
+            assert_eq!(dnode.value_canonical().as_deref(), Some("false"))
+        }
+        outcome => panic!("Unexpected outcome: {:?}", outcome),
+    }
+    assert!(matches!(
+        dtree.new_or_update_path(path, Some("false"), false),
+        Ok(NewPathOutcome::Unchanged)
+    ));
+
+    let path = "/ietf-interfaces:interfaces/interface[name='eth/0/2']/\
+                description";
+    match dtree.new_or_update_path(path, Some("MGMT"), false) {
+        Ok(NewPathOutcome::Created(dnode)) => assert_eq!(dnode.path(), path),
+        outcome => panic!("Unexpected outcome: {:?}", outcome),
+    }
+}
+
+#[test]
+fn data_apply_subtree_filter() {
+    let ctx = create_context();
+    let dtree = parse_json_data(&ctx, JSON_TREE1);
+    let filter = |filter: &str| {
+        let filter = DataTree::parse_string(
+            &ctx,
+            filter,
+            DataFormat::XML,
+            DataParserFlags::NO_VALIDATION | DataParserFlags::OPAQ,
+            DataValidationFlags::empty(),
+        )
+        .expect("Failed to parse filter");
+        dtree
+            .apply_subtree_filter(&filter)
+            .expect("Failed to apply filter")
+    };
+    let names = |dtree: &DataTree| {
+        dtree
+            .collect_values("/ietf-interfaces:interfaces/interface/name")
+            .expect("Failed to find nodes")
+    };
+
+    // Content match nodes only: the whole list entry is selected.
+    let result = filter(
+        r###"<interfaces xmlns="urn:ietf:params:xml:ns:yang:ietf-interfaces">
+               <interface><name>eth/0/0</name></interface>
+             </interfaces>"###,
+    );
+    assert_eq!(names(&result), vec!["eth/0/0"]);
+    assert!(result
+        .find_path(
+            "/ietf-interfaces:interfaces/interface[name='eth/0/0']/enabled",
+            false
+        )
+        .is_ok());
+
+    // Content match and selection nodes.
+    let result = filter(
+        r###"<interfaces xmlns="urn:ietf:params:xml:ns:yang:ietf-interfaces">
+               <interface><name>eth/0/1</name><description/></interface>
+             </interfaces>"###,
+    );
+    assert_eq!(names(&result), vec!["eth/0/1"]);
+    assert_eq!(
+        result
+            .collect_values("/ietf-interfaces:interfaces/interface/*")
+            .expect("Failed to find nodes"),
+        vec!["eth/0/1", "MKT"]
+    );
+
+    // Selection node in all list entries.
+    let result = filter(
+        r###"<interfaces xmlns="urn:ietf:params:xml:ns:yang:ietf-interfaces">
+               <interface><enabled/></interface>
+             </interfaces>"###,
+    );
+    assert_eq!(names(&result), vec!["eth/0/0", "eth/0/1"]);
+    assert!(result
+        .find_path(
+            "/ietf-interfaces:interfaces/interface[name='eth/0/1']/description",
+            false
+        )
+        .is_err());
+
+    // Failed content match.
+    let result = filter(
+        r###"<interfaces xmlns="urn:ietf:params:xml:ns:yang:ietf-interfaces">
+               <interface><name>eth/0/9</name></interface>
+             </interfaces>"###,
+    );
+    assert!(result.reference().is_none());
+}
+
+#[test]
+fn data_parse_fast() {
+    let ctx = create_context();
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+    let dtree2 = DataTree::parse_fast(&ctx, JSON_TREE1, DataFormat::JSON)
+        .expect("Failed to parse data tree");
+
+    assert_data_eq!(&dtree1, &dtree2);
+}
+
 #[test]
 fn data_validate() {
     let ctx = create_context();
@@ -361,7 +613,10 @@ fn data_duplicate_subtree() {
     let dtree1 = parse_json_data(&ctx, JSON_TREE1);
 
     let dnode = dtree1
-        .find_path("/ietf-interfaces:interfaces/interface[name='eth/0/0']")
+        .find_path(
+            "/ietf-interfaces:interfaces/interface[name='eth/0/0']",
+            false,
+        )
         .expect("Failed to lookup data");
 
     // Duplicate without parents.
@@ -399,6 +654,18 @@ fn data_duplicate_subtree() {
     );
 }
 
+#[test]
+fn data_borrow_raw() {
+    let ctx = create_context();
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+
+    // Dropping the non-owning data tree must not free the original nodes.
+    let view = unsafe { DataTree::borrow_raw(&ctx, dtree1.raw()) };
+    assert_data_eq!(&view, &dtree1);
+    drop(view);
+    assert_eq!(dtree1.traverse().count(), 11);
+}
+
 #[test]
 fn data_merge() {
     let ctx = create_context();
@@ -410,6 +677,562 @@ fn data_merge() {
     assert_data_eq!(&dtree1, &dtree_merge);
 }
 
+#[test]
+fn data_merge_validate() {
+    let ctx = create_context();
+    let mut dtree1 = parse_json_data(&ctx, JSON_TREE1);
+    let dtree2 = parse_json_data(&ctx, JSON_TREE2);
+    let dtree_merge = parse_json_data(&ctx, JSON_MERGE);
+    let options = DataValidationFlags::NO_STATE | DataValidationFlags::PRESENT;
+
+    // Interface without the mandatory "type" leaf.
+    let mut dtree3 = DataTree::new(&ctx);
+    dtree3
+        .new_path(
+            "/ietf-interfaces:interfaces/interface[name='eth/0/9']/enabled",
+            Some("true"),
+            false,
+        )
+        .expect("Failed to edit data tree");
+    assert!(dtree1.merge_validate(&dtree3, options).is_err());
+    assert_data_eq!(&dtree1, &parse_json_data(&ctx, JSON_TREE1));
+
+    // The data tree is merged in place, preserving the user data of its
+    // nodes.
+    let path = "/ietf-interfaces:interfaces/interface[name='eth/0/0']";
+    dtree1
+        .set_user_data(path, 1u32)
+        .expect("Failed to set user data");
+    dtree1
+        .merge_validate(&dtree2, options)
+        .expect("Failed to merge data trees");
+    assert_data_eq!(&dtree1, &dtree_merge);
+    assert_eq!(
+        dtree1
+            .find_path(path, false)
+            .expect("Failed to lookup data")
+            .user_data::<u32>(),
+        Some(&1)
+    );
+}
+
+#[test]
+fn data_validate_with_dependencies() {
+    let ctx = create_context();
+    let options = DataValidationFlags::NO_STATE | DataValidationFlags::PRESENT;
+    let deps = parse_json_data(
+        &ctx,
+        r###"{"yang2-test:misc": {"user-ordered": ["a", "b"]}}"###,
+    );
+    let deps_copy = deps.duplicate().expect("Failed to duplicate data");
+
+    // The leafref can't be resolved without the dependency data.
+    let data = r###"{"yang2-test:misc": {"reference": "a"}}"###;
+    let mut dtree = parse_json_data(&ctx, data);
+    assert!(dtree.validate(options).is_err());
+
+    let mut dtree = parse_json_data(&ctx, data);
+    dtree
+        .validate_with_dependencies(&deps, options)
+        .expect("Failed to validate data");
+    assert!(dtree.find_path("/yang2-test:misc/reference", false).is_ok());
+    assert_eq!(
+        dtree
+            .find_xpath("/yang2-test:misc/user-ordered")
+            .expect("Failed to lookup data")
+            .count(),
+        0
+    );
+    assert_data_eq!(&deps, &deps_copy);
+
+    let mut dtree =
+        parse_json_data(&ctx, r###"{"yang2-test:misc": {"reference": "c"}}"###);
+    assert!(dtree.validate_with_dependencies(&deps, options).is_err());
+    assert_data_eq!(&deps, &deps_copy);
+}
+
+#[test]
+fn data_extract_module() {
+    let ctx = create_context();
+    let mut dtree = parse_json_data(&ctx, JSON_TREE1);
+    dtree
+        .merge(&parse_json_data(
+            &ctx,
+            r###"{"yang2-test:misc": {"user-ordered": ["a"]}}"###,
+        ))
+        .expect("Failed to merge data trees");
+
+    let module = ctx
+        .get_module_latest("ietf-interfaces")
+        .expect("Failed to find module");
+    let mut interfaces = dtree
+        .extract_module(&module)
+        .expect("Failed to extract module data");
+    assert_data_eq!(&interfaces, &parse_json_data(&ctx, JSON_TREE1));
+    interfaces
+        .validate(DataValidationFlags::NO_STATE | DataValidationFlags::PRESENT)
+        .expect("Failed to validate data");
+
+    let module = ctx
+        .get_module_latest("ietf-routing")
+        .expect("Failed to find module");
+    let routing = dtree
+        .extract_module(&module)
+        .expect("Failed to extract module data");
+    assert!(routing.reference().is_none());
+}
+
+#[test]
+fn data_store_builder() {
+    let ctx = create_context();
+    let options = DataValidationFlags::NO_STATE | DataValidationFlags::PRESENT;
+    let interfaces = parse_json_data(&ctx, JSON_TREE1);
+    let misc = parse_json_data(
+        &ctx,
+        r###"{"yang2-test:misc": {"user-ordered": ["a"], "reference": "a"}}"###,
+    );
+
+    let mut builder = DataStoreBuilder::new(&ctx);
+    builder
+        .add("ietf-interfaces", &interfaces)
+        .expect("Failed to add data");
+    builder
+        .add("yang2-test", &misc)
+        .expect("Failed to add data");
+    let dtree = builder.build(options).expect("Failed to build data store");
+    assert!(dtree
+        .find_path(
+            "/ietf-interfaces:interfaces/interface[name='eth/0/0']",
+            false
+        )
+        .is_ok());
+    assert!(dtree.find_path("/yang2-test:misc/reference", false).is_ok());
+
+    // Data of the wrong module or added twice.
+    let mut builder = DataStoreBuilder::new(&ctx);
+    assert!(builder.add("yang2-test", &interfaces).is_err());
+    builder
+        .add("ietf-interfaces", &interfaces)
+        .expect("Failed to add data");
+    assert!(builder.add("ietf-interfaces", &interfaces).is_err());
+
+    // Validation errors are attributed to the contributing module.
+    let misc =
+        parse_json_data(&ctx, r###"{"yang2-test:misc": {"reference": "a"}}"###);
+    builder
+        .add("yang2-test", &misc)
+        .expect("Failed to add data");
+    let error = builder.build(options).unwrap_err();
+    assert!(error.msg.unwrap().starts_with("Module \"yang2-test\": "));
+}
+
+#[test]
+fn data_parse_into() {
+    let ctx = create_context();
+    let mut dtree = parse_json_data(&ctx, JSON_TREE1);
+
+    dtree
+        .parse_into(
+            JSON_MERGE,
+            DataFormat::JSON,
+            DataParserFlags::NO_VALIDATION,
+            DataValidationFlags::empty(),
+        )
+        .expect("Failed to parse data into the data tree");
+    let mut dtree2 = parse_json_data(&ctx, JSON_TREE1);
+    dtree2
+        .merge(&parse_json_data(&ctx, JSON_MERGE))
+        .expect("Failed to merge data trees");
+    assert_data_eq!(&dtree, &dtree2);
+
+    // Invalid data leaves the data tree untouched.
+    assert!(dtree
+        .parse_into(
+            r###"{"ietf-interfaces:interfaces": {"interface": [{
+                "name": "eth/0/9"
+            }]}}"###,
+            DataFormat::JSON,
+            DataParserFlags::empty(),
+            DataValidationFlags::NO_STATE,
+        )
+        .is_err());
+    assert_data_eq!(&dtree, &dtree2);
+}
+
+#[test]
+fn data_yang_patch() {
+    let ctx = create_context();
+    let options = DataValidationFlags::NO_STATE | DataValidationFlags::PRESENT;
+    let edit = |edit_id: &str,
+                operation: YangPatchOperation,
+                target: &str,
+                value: Option<&str>| YangPatchEdit {
+        edit_id: edit_id.to_owned(),
+        operation,
+        target: target.to_owned(),
+        point: None,
+        position: None,
+        value: value.map(str::to_owned),
+    };
+
+    // Edits are applied in order.
+    let mut dtree = parse_json_data(&ctx, JSON_TREE1);
+    let patch = YangPatch {
+        patch_id: "interfaces".to_owned(),
+        comment: None,
+        edits: vec![
+            edit(
+                "1",
+                YangPatchOperation::Create,
+                "/interface=eth%2F0%2F2",
+                Some(
+                    r###"{"ietf-interfaces:interface": [{
+                        "name": "eth/0/2",
+                        "type": "iana-if-type:ethernetCsmacd"
+                    }]}"###,
+                ),
+            ),
+            edit(
+                "2",
+                YangPatchOperation::Merge,
+                "/interface=eth%2F0%2F0/description",
+                Some(r###"{"ietf-interfaces:description": "ENG2"}"###),
+            ),
+            edit(
+                "3",
+                YangPatchOperation::Delete,
+                "/interface=eth%2F0%2F1",
+                None,
+            ),
+        ],
+    };
+    patch
+        .apply(&mut dtree, "/ietf-interfaces:interfaces", options)
+        .expect("Failed to apply patch");
+    assert_eq!(
+        dtree
+            .collect_values("/ietf-interfaces:interfaces/interface/name")
+            .expect("Failed to find nodes"),
+        vec!["eth/0/0", "eth/0/2"]
+    );
+    assert_eq!(
+        dtree
+            .find_path(
+                "/ietf-interfaces:interfaces/interface[name='eth/0/0']/\
+                 description",
+                false
+            )
+            .expect("Failed to find node")
+            .value_canonical()
+            .as_deref(),
+        Some("ENG2")
+    );
+
+    // Failed edits are reported with their edit-id and leave the data tree
+    // untouched.
+    let original = dtree.duplicate().expect("Failed to duplicate data");
+    let patch = YangPatch {
+        patch_id: "invalid".to_owned(),
+        comment: None,
+        edits: vec![
+            edit(
+                "remove",
+                YangPatchOperation::Remove,
+                "/interface=eth%2F0%2F2",
+                None,
+            ),
+            edit(
+                "delete",
+                YangPatchOperation::Delete,
+                "/interface=eth%2F0%2F9",
+                None,
+            ),
+        ],
+    };
+    let error = patch
+        .apply(&mut dtree, "/ietf-interfaces:interfaces", options)
+        .unwrap_err();
+    assert!(error.msg.unwrap().starts_with("Edit \"delete\": "));
+    assert_data_eq!(&dtree, &original);
+
+    // Inserting and moving user-ordered leaf-list values.
+    let mut dtree = parse_json_data(
+        &ctx,
+        r###"{"yang2-test:misc": {"user-ordered": ["a", "b"]}}"###,
+    );
+    let mut insert = edit(
+        "insert",
+        YangPatchOperation::Insert,
+        "/user-ordered=c",
+        Some(r###"{"yang2-test:user-ordered": ["c"]}"###),
+    );
+    insert.position = Some(YangPatchWhere::First);
+    let mut move_after =
+        edit("move", YangPatchOperation::Move, "/user-ordered=a", None);
+    move_after.position = Some(YangPatchWhere::After);
+    move_after.point = Some("/user-ordered=b".to_owned());
+    let patch = YangPatch {
+        patch_id: "order".to_owned(),
+        comment: None,
+        edits: vec![insert, move_after],
+    };
+    patch
+        .apply(&mut dtree, "/yang2-test:misc", options)
+        .expect("Failed to apply patch");
+    assert_eq!(
+        dtree
+            .collect_values("/yang2-test:misc/user-ordered")
+            .expect("Failed to find nodes"),
+        vec!["c", "b", "a"]
+    );
+}
+
+#[test]
+fn data_yang_patch_keys() {
+    let ctx = create_context();
+    let options = DataValidationFlags::NO_STATE | DataValidationFlags::PRESENT;
+    let create = |target: &str, name: &str| YangPatchEdit {
+        edit_id: name.to_owned(),
+        operation: YangPatchOperation::Create,
+        target: target.to_owned(),
+        point: None,
+        position: None,
+        value: Some(format!(
+            r###"{{"ietf-interfaces:interface": [{{
+                "name": "{}",
+                "type": "iana-if-type:ethernetCsmacd"
+            }}]}}"###,
+            name
+        )),
+    };
+    let patch = |edits| YangPatch {
+        patch_id: "keys".to_owned(),
+        comment: None,
+        edits,
+    };
+
+    // Key values are percent-decoded and quoted as needed.
+    let mut dtree = DataTree::new(&ctx);
+    patch(vec![
+        create("/interface=eth0%2C1", "eth0,1"),
+        create("/interface=it's", "it's"),
+        create("/interface=eth0%252C1", "eth0%2C1"),
+    ])
+    .apply(&mut dtree, "/ietf-interfaces:interfaces", options)
+    .expect("Failed to apply patch");
+    let mut names = dtree
+        .collect_values("/ietf-interfaces:interfaces/interface/name")
+        .expect("Failed to find nodes");
+    names.sort();
+    assert_eq!(names, vec!["eth0%2C1", "eth0,1", "it's"]);
+    assert!(dtree
+        .find_path(
+            "/ietf-interfaces:interfaces/interface[name=\"it's\"]",
+            false
+        )
+        .is_ok());
+
+    // Unencoded commas separate the key values of multi-key lists.
+    let mut edit = create("/interface=eth0,1", "eth0,1");
+    edit.operation = YangPatchOperation::Remove;
+    assert!(patch(vec![edit])
+        .apply(&mut dtree, "/ietf-interfaces:interfaces", options)
+        .is_err());
+
+    // Invalid percent-encodings are rejected.
+    for target in &["/interface=eth0%2", "/interface=eth0%+1"] {
+        let mut edit = create(target, "eth0");
+        edit.operation = YangPatchOperation::Remove;
+        assert!(patch(vec![edit])
+            .apply(&mut dtree, "/ietf-interfaces:interfaces", options)
+            .is_err());
+    }
+}
+
+#[test]
+fn data_yang_patch_parse_json() {
+    let ctx = create_context();
+    let options = DataValidationFlags::NO_STATE | DataValidationFlags::PRESENT;
+
+    let patch = YangPatch::parse_json(
+        &ctx,
+        r###"{
+            "ietf-yang-patch:yang-patch": {
+                "patch-id": "interfaces",
+                "comment": "Replace an interface",
+                "edit": [
+                    {
+                        "edit-id": "1",
+                        "operation": "create",
+                        "target": "/interface=eth%2F0%2F2",
+                        "value": {
+                            "ietf-interfaces:interface": [{
+                                "name": "eth/0/2",
+                                "type": "iana-if-type:ethernetCsmacd"
+                            }]
+                        }
+                    },
+                    {
+                        "edit-id": "2",
+                        "operation": "remove",
+                        "target": "/interface=eth%2F0%2F1"
+                    }
+                ]
+            }
+        }"###,
+    )
+    .expect("Failed to parse patch");
+    assert_eq!(patch.patch_id, "interfaces");
+    assert_eq!(patch.comment.as_deref(), Some("Replace an interface"));
+    assert_eq!(patch.edits.len(), 2);
+    assert_eq!(patch.edits[0].edit_id, "1");
+    assert_eq!(patch.edits[0].operation, YangPatchOperation::Create);
+    assert_eq!(patch.edits[0].target, "/interface=eth%2F0%2F2");
+    assert!(patch.edits[0].value.is_some());
+    assert_eq!(patch.edits[1].operation, YangPatchOperation::Remove);
+    assert!(patch.edits[1].value.is_none());
+
+    let mut dtree = parse_json_data(&ctx, JSON_TREE1);
+    patch
+        .apply(&mut dtree, "/ietf-interfaces:interfaces", options)
+        .expect("Failed to apply patch");
+    assert_eq!(
+        dtree
+            .collect_values("/ietf-interfaces:interfaces/interface/name")
+            .expect("Failed to find nodes"),
+        vec!["eth/0/0", "eth/0/2"]
+    );
+
+    let patch = YangPatch::parse_json(
+        &ctx,
+        r###"{
+            "ietf-yang-patch:yang-patch": {
+                "patch-id": "order",
+                "edit": [
+                    {
+                        "edit-id": "insert",
+                        "operation": "insert",
+                        "target": "/user-ordered=c",
+                        "point": "/user-ordered=b",
+                        "where": "before",
+                        "value": {"yang2-test:user-ordered": ["c"]}
+                    }
+                ]
+            }
+        }"###,
+    )
+    .expect("Failed to parse patch");
+    assert_eq!(patch.edits[0].point.as_deref(), Some("/user-ordered=b"));
+    assert_eq!(patch.edits[0].position, Some(YangPatchWhere::Before));
+
+    let mut dtree = parse_json_data(
+        &ctx,
+        r###"{"yang2-test:misc": {"user-ordered": ["a", "b"]}}"###,
+    );
+    patch
+        .apply(&mut dtree, "/yang2-test:misc", options)
+        .expect("Failed to apply patch");
+    assert_eq!(
+        dtree
+            .collect_values("/yang2-test:misc/user-ordered")
+            .expect("Failed to find nodes"),
+        vec!["a", "c", "b"]
+    );
+
+    // The document is validated against the YANG Patch schema.
+    for data in &[
+        r###"{"yang-patch": {"patch-id": "1"}}"###,
+        r###"{"ietf-yang-patch:yang-patch": {}}"###,
+        r###"{"ietf-yang-patch:yang-patch": {
+            "patch-id": "1",
+            "edit": [{"edit-id": "1", "operation": "copy", "target": "/"}]
+        }}"###,
+    ] {
+        assert!(YangPatch::parse_json(&ctx, data).is_err());
+    }
+}
+
+#[test]
+fn data_is_complete() {
+    let ctx = create_context();
+    let dtree = parse_json_data(&ctx, JSON_TREE1);
+    let node_count = dtree.node_count();
+    assert!(dtree.is_complete());
+    assert_eq!(dtree.node_count(), node_count);
+
+    let dtree = parse_json_data(
+        &ctx,
+        r###"{"ietf-interfaces:interfaces": {"interface": [{
+            "name": "eth/0/9"
+        }]}}"###,
+    );
+    assert!(!dtree.is_complete());
+}
+
+#[test]
+fn data_merge_diff() {
+    let ctx = create_context();
+    let mut dtree1 = parse_json_data(&ctx, JSON_TREE1);
+    let dtree2 = parse_json_data(&ctx, JSON_TREE2);
+    let dtree_merge = parse_json_data(&ctx, JSON_MERGE);
+
+    let diff = dtree1
+        .merge_diff(
+            &dtree2,
+            DataValidationFlags::NO_STATE | DataValidationFlags::PRESENT,
+        )
+        .expect("Failed to merge data trees");
+    assert_data_eq!(&dtree1, &dtree_merge);
+    assert_eq!(
+        diff.iter()
+            .map(|(op, dnode, is_default)| (op, dnode.path(), is_default))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                DataDiffOp::Replace,
+                "/ietf-interfaces:interfaces/interface[name='eth/0/0']/enabled"
+                    .to_owned(),
+                false
+            ),
+            (
+                DataDiffOp::Create,
+                "/ietf-interfaces:interfaces/interface[name='eth/0/2']"
+                    .to_owned(),
+                false
+            ),
+        ]
+    );
+}
+
+#[test]
+fn data_equivalent() {
+    let ctx = create_context();
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+    let dtree2 = parse_json_data(&ctx, JSON_TREE2);
+    let dup = dtree1.duplicate().expect("Failed to duplicate data tree");
+
+    assert!(dtree1.equivalent(&dup, DataCompareFlags::empty()));
+    assert!(!dtree1.equivalent(&dtree2, DataCompareFlags::empty()));
+    assert!(!dtree1.equivalent_unordered(&dtree2, DataCompareFlags::empty()));
+
+    // Same data, but with the list entries added in reverse order.
+    let mut dtree3 = DataTree::new(&ctx);
+    for (name, description) in &[("eth/0/1", "MKT"), ("eth/0/0", "ENG")] {
+        let xpath =
+            format!("/ietf-interfaces:interfaces/interface[name='{}']", name);
+        for (leaf, value) in &[
+            ("description", *description),
+            ("type", "iana-if-type:ethernetCsmacd"),
+            ("enabled", "true"),
+        ] {
+            dtree3
+                .new_path(&format!("{}/{}", xpath, leaf), Some(value), false)
+                .expect("Failed to edit data tree");
+        }
+    }
+    assert!(dtree1.equivalent_unordered(&dtree3, DataCompareFlags::empty()));
+}
+
 #[test]
 fn data_add_implicit() {
     let ctx = create_context();
@@ -482,9 +1305,394 @@ fn data_diff() {
 }
 
 #[test]
-fn data_diff_apply() {
+fn data_diff_eq() {
     let ctx = create_context();
-    let mut dtree1 = parse_json_data(&ctx, JSON_TREE1);
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+    let dtree2 = parse_json_data(&ctx, JSON_TREE2);
+
+    let diff = dtree1
+        .diff(&dtree2, DataDiffFlags::DEFAULTS)
+        .expect("Failed to compare data trees");
+    assert_eq!(diff, parse_json_diff(&ctx, JSON_DIFF));
+    assert_ne!(diff, parse_json_diff(&ctx, JSON_RDIFF));
+
+    let rdiff = diff.reverse().expect("Failed to reverse diff");
+    assert_eq!(rdiff, parse_json_diff(&ctx, JSON_RDIFF));
+    assert_eq!(
+        rdiff.reverse().expect("Failed to reverse diff"),
+        parse_json_diff(&ctx, JSON_DIFF)
+    );
+}
+
+#[test]
+fn data_by_content() {
+    let ctx = create_context();
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+    let dtree2 = parse_json_data(&ctx, JSON_TREE2);
+    let dtree3 = dtree1.duplicate().expect("Failed to duplicate data");
+
+    assert_eq!(ByContent(&dtree1), ByContent(&dtree1));
+    assert_eq!(ByContent(&dtree1), ByContent(&dtree3));
+    assert_ne!(ByContent(&dtree1), ByContent(&dtree2));
+
+    // Data node references are still compared by identity.
+    assert_ne!(dtree1.reference(), dtree3.reference());
+}
+
+#[test]
+fn data_fingerprint() {
+    let ctx = create_context();
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+    let dtree2 = parse_json_data(&ctx, JSON_TREE2);
+    let fingerprint =
+        |dtree: &DataTree| dtree.fingerprint().expect("Failed to print data");
+
+    assert_eq!(fingerprint(&dtree1), fingerprint(&dtree1));
+    assert_eq!(
+        fingerprint(&dtree1),
+        fingerprint(&dtree1.duplicate().expect("Failed to duplicate data"))
+    );
+    assert_ne!(fingerprint(&dtree1), fingerprint(&dtree2));
+    assert_ne!(fingerprint(&dtree1), fingerprint(&DataTree::new(&ctx)));
+}
+
+#[test]
+fn data_diff_changes() {
+    let ctx = create_context();
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+    let dtree2 = parse_json_data(&ctx, JSON_TREE2);
+
+    let diff = dtree1
+        .diff(&dtree2, DataDiffFlags::empty())
+        .expect("Failed to compare data trees");
+    assert_eq!(
+        diff.changes()
+            .map(|change| (
+                change.op,
+                change.node.path(),
+                change.old_value,
+                change.new_value
+            ))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                DataDiffOp::Replace,
+                "/ietf-interfaces:interfaces/interface[name='eth/0/0']/enabled"
+                    .to_owned(),
+                Some("true".to_owned()),
+                Some("false".to_owned()),
+            ),
+            (
+                DataDiffOp::Delete,
+                "/ietf-interfaces:interfaces/interface[name='eth/0/1']"
+                    .to_owned(),
+                None,
+                None,
+            ),
+            (
+                DataDiffOp::Create,
+                "/ietf-interfaces:interfaces/interface[name='eth/0/2']"
+                    .to_owned(),
+                None,
+                None,
+            ),
+        ]
+    );
+}
+
+#[test]
+fn data_diff_summary() {
+    let ctx = create_context();
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+    let dtree2 = parse_json_data(&ctx, JSON_TREE2);
+
+    let diff = dtree1
+        .diff(&dtree2, DataDiffFlags::empty())
+        .expect("Failed to compare data trees");
+    let summary = diff.summary();
+    assert_eq!(
+        summary,
+        DiffSummary {
+            created: 1,
+            deleted: 1,
+            replaced: 1,
+        }
+    );
+    assert_eq!(summary.total(), 3);
+    assert_eq!(summary.to_string(), "1 created, 1 deleted, 1 replaced");
+
+    let diff = dtree1
+        .diff(&dtree1, DataDiffFlags::empty())
+        .expect("Failed to compare data trees");
+    assert_eq!(diff.summary(), DiffSummary::default());
+    assert_eq!(diff.summary().total(), 0);
+}
+
+#[test]
+fn data_diff_anydata() {
+    let ctx = create_context();
+    let dtree1 = parse_json_data(
+        &ctx,
+        r###"{"yang2-test:misc": {"any-data": {"foo": "bar"}}}"###,
+    );
+    let dtree2 = parse_json_data(
+        &ctx,
+        r###"{"yang2-test:misc": {"any-data": {"foo": "baz"}}}"###,
+    );
+
+    let diff = dtree1
+        .diff(&dtree2, DataDiffFlags::empty())
+        .expect("Failed to compare data trees");
+    let changes = diff.changes().collect::<Vec<_>>();
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].op, DataDiffOp::Replace);
+    assert_eq!(changes[0].node.path(), "/yang2-test:misc/any-data");
+    assert!(changes[0].old_value.as_deref().unwrap().contains("bar"));
+    assert!(changes[0].new_value.as_deref().unwrap().contains("baz"));
+
+    let mut dtree3 = dtree1.duplicate().expect("Failed to duplicate data");
+    dtree3.diff_apply(&diff).expect("Failed to apply diff");
+    assert_data_eq!(&dtree3, &dtree2);
+}
+
+#[test]
+fn data_find_meta() {
+    let ctx = create_context();
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+    let dtree2 = parse_json_data(&ctx, JSON_TREE2);
+
+    let diff = dtree1
+        .diff(&dtree2, DataDiffFlags::empty())
+        .expect("Failed to compare data trees");
+    for (_, dnode, _) in diff.iter() {
+        let meta = dnode
+            .find_meta(Some("yang"), "operation")
+            .expect("Failed to find metadata");
+        assert_eq!(meta.module().name(), "yang");
+        assert_eq!(Some(meta), dnode.find_meta(None, "operation"));
+        assert!(dnode.find_meta(Some("ietf-netconf"), "operation").is_none());
+        assert!(dnode.find_meta(Some("yang"), "unknown").is_none());
+    }
+}
+
+#[test]
+fn data_meta_value_typed() {
+    let ctx = create_context();
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+    let dtree2 = parse_json_data(&ctx, JSON_TREE2);
+
+    let diff = dtree1
+        .diff(&dtree2, DataDiffFlags::empty())
+        .expect("Failed to compare data trees");
+    for (_, dnode, _) in diff.iter() {
+        for meta in dnode.meta() {
+            let expected = match meta.name() {
+                "orig-default" => DataValue::Bool(meta.value() == "true"),
+                _ => DataValue::Other(meta.value().to_owned()),
+            };
+            assert_eq!(meta.value_typed(), Some(expected));
+        }
+    }
+}
+
+#[test]
+fn data_edit_operation() {
+    // Minimal ietf-netconf module, defining only the "operation" annotation.
+    static IETF_NETCONF: &str = r###"
+        module ietf-netconf {
+          namespace "urn:ietf:params:xml:ns:netconf:base:1.0";
+          prefix nc;
+          import ietf-yang-metadata {
+            prefix md;
+          }
+          md:annotation operation {
+            type enumeration {
+              enum merge;
+              enum replace;
+              enum create;
+              enum delete;
+              enum remove;
+              enum none;
+            }
+          }
+        }"###;
+    let mut modules = EmbeddedModules::new();
+    modules.insert(
+        EmbeddedModuleKey::new("ietf-netconf", None, None, None),
+        IETF_NETCONF,
+    );
+
+    let mut ctx = Context::new(ContextFlags::NO_YANGLIBRARY)
+        .expect("Failed to create context");
+    ctx.set_searchdir(SEARCH_DIR)
+        .expect("Failed to set YANG search directory");
+    ctx.set_embedded_modules(&modules);
+    for module_name in &["ietf-netconf", "iana-if-type", "ietf-interfaces"] {
+        ctx.load_module(module_name, None, &[])
+            .expect("Failed to load module");
+    }
+    let ctx = Arc::new(ctx);
+
+    let dtree = parse_json_data(
+        &ctx,
+        r###"{
+            "ietf-interfaces:interfaces": {
+                "@": {"ietf-netconf:operation": "merge"},
+                "interface": [
+                    {
+                        "@": {"ietf-netconf:operation": "replace"},
+                        "name": "eth/0/0",
+                        "description": "ENG",
+                        "@description": {"ietf-netconf:operation": "remove"}
+                    }
+                ]
+            }
+        }"###,
+    );
+    let path = "/ietf-interfaces:interfaces";
+    let operation = |path: &str| {
+        dtree
+            .find_path(path, false)
+            .expect("Failed to lookup data")
+            .edit_operation()
+    };
+    assert_eq!(operation(path), Some(EditOperation::Merge));
+    let path = "/ietf-interfaces:interfaces/interface[name='eth/0/0']";
+    assert_eq!(operation(path), Some(EditOperation::Replace));
+    assert_eq!(
+        operation(&format!("{}/description", path)),
+        Some(EditOperation::Remove)
+    );
+    assert_eq!(operation(&format!("{}/name", path)), None);
+}
+
+#[test]
+fn data_copy_meta_from() {
+    let ctx = create_context();
+    let mut dtree1 = parse_json_data(&ctx, JSON_TREE1);
+    let mut dtree2 = parse_json_data(&ctx, JSON_TREE2);
+
+    let diff = dtree1
+        .diff(&dtree2, DataDiffFlags::empty())
+        .expect("Failed to compare data trees");
+    for (_, dnode, _) in diff.iter() {
+        let operation = dnode.find_meta(Some("yang"), "operation").unwrap();
+        let path = dnode.path();
+        let target = if dtree2.find_path(&path, false).is_ok() {
+            &mut dtree2
+        } else {
+            &mut dtree1
+        };
+        assert!(target
+            .find_path(&path, false)
+            .expect("Failed to lookup data")
+            .meta()
+            .next()
+            .is_none());
+
+        target
+            .copy_meta_from(&path, &dnode)
+            .expect("Failed to copy metadata");
+        assert_eq!(
+            target
+                .find_path(&path, false)
+                .expect("Failed to lookup data")
+                .find_meta(Some("yang"), "operation")
+                .map(|meta| meta.value().to_owned()),
+            Some(operation.value().to_owned())
+        );
+
+        // Copying the same metadata again replaces it.
+        target
+            .copy_meta_from(&path, &dnode)
+            .expect("Failed to copy metadata");
+        assert_eq!(
+            target
+                .find_path(&path, false)
+                .expect("Failed to lookup data")
+                .meta()
+                .count(),
+            dnode.meta().count()
+        );
+    }
+}
+
+#[test]
+fn data_diff_defaults() {
+    let ctx = create_context();
+    let mut dtree1 = DataTree::new(&ctx);
+    dtree1
+        .new_path(
+            "/ietf-interfaces:interfaces/interface[name='eth/0/0']/type",
+            Some("iana-if-type:ethernetCsmacd"),
+            false,
+        )
+        .expect("Failed to edit data tree");
+
+    // Add the default "enabled" leaf, among other implicit nodes.
+    let mut dtree2 = dtree1.duplicate().expect("Failed to duplicate data");
+    dtree2
+        .add_implicit(DataImplicitFlags::NO_STATE)
+        .expect("Failed to add implicit nodes");
+
+    let diff = dtree1
+        .diff(&dtree2, DataDiffFlags::DEFAULTS)
+        .expect("Failed to compare data trees");
+    assert!(diff.iter().any(|(op, dnode, is_default)| {
+        op == DataDiffOp::Create
+            && dnode.path()
+                == "/ietf-interfaces:interfaces/interface[name='eth/0/0']/enabled"
+            && is_default
+    }));
+    assert!(diff.iter().all(|(_, _, is_default)| is_default));
+}
+
+#[test]
+fn data_diff_orig_default() {
+    let ctx = create_context();
+    let diff = parse_json_diff(
+        &ctx,
+        r###"
+        {
+          "ietf-interfaces:interfaces": {
+            "@": {
+              "yang:operation": "none"
+            },
+            "interface": [
+              {
+                "name": "eth/0/0",
+                "enabled": false,
+                "@enabled": {
+                  "yang:operation": "replace",
+                  "yang:orig-default": true,
+                  "yang:orig-value": "true"
+                }
+              }
+            ]
+          }
+        }"###,
+    );
+    assert_eq!(
+        diff.iter()
+            .map(|(op, dnode, is_default)| (op, dnode.path(), is_default))
+            .collect::<Vec<_>>(),
+        vec![(
+            DataDiffOp::Replace,
+            "/ietf-interfaces:interfaces/interface[name='eth/0/0']/enabled"
+                .to_owned(),
+            true
+        )]
+    );
+
+    // Explicit values in a parsed diff aren't default changes.
+    let diff = parse_json_diff(&ctx, JSON_DIFF);
+    assert!(diff.iter().all(|(_, _, is_default)| !is_default));
+}
+
+#[test]
+fn data_diff_apply() {
+    let ctx = create_context();
+    let mut dtree1 = parse_json_data(&ctx, JSON_TREE1);
     let dtree2 = parse_json_data(&ctx, JSON_TREE2);
 
     let diff = dtree1
@@ -495,6 +1703,23 @@ fn data_diff_apply() {
     assert_data_eq!(&dtree1, &dtree2);
 }
 
+#[test]
+fn data_diff_apply_check() {
+    let ctx = create_context();
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+    let dtree2 = parse_json_data(&ctx, JSON_TREE2);
+
+    let diff = dtree1
+        .diff(&dtree2, DataDiffFlags::DEFAULTS)
+        .expect("Failed to compare data trees");
+    assert!(dtree1.diff_apply_check(&diff).is_ok());
+    assert!(dtree2.diff_apply_check(&diff).is_err());
+
+    // The data trees must not be modified.
+    assert_data_eq!(&dtree1, &parse_json_data(&ctx, JSON_TREE1));
+    assert_data_eq!(&dtree2, &parse_json_data(&ctx, JSON_TREE2));
+}
+
 #[test]
 fn data_diff_reverse() {
     let ctx = create_context();
@@ -535,6 +1760,146 @@ fn data_iterator_traverse() {
     );
 }
 
+#[test]
+fn data_iterator_traverse_depth() {
+    let ctx = create_context();
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+    let dnode = dtree1
+        .find_path("/ietf-interfaces:interfaces", false)
+        .expect("Failed to lookup data");
+
+    assert_eq!(
+        dnode
+            .traverse_depth(1)
+            .map(|(dnode, depth)| (dnode.path(), depth))
+            .collect::<Vec<(String, usize)>>(),
+        vec![
+            ("/ietf-interfaces:interfaces".to_owned(), 0),
+            (
+                "/ietf-interfaces:interfaces/interface[name='eth/0/0']"
+                    .to_owned(),
+                1
+            ),
+            (
+                "/ietf-interfaces:interfaces/interface[name='eth/0/1']"
+                    .to_owned(),
+                1
+            ),
+        ]
+    );
+    assert_eq!(dnode.traverse_depth(0).count(), 1);
+    assert_eq!(dnode.traverse_depth(2).count(), dnode.traverse().count());
+}
+
+#[test]
+fn data_iterator_traverse_config() {
+    let ctx = create_context();
+    let mut dtree = DataTree::new(&ctx);
+    for (xpath, value) in &[
+        (
+            "/ietf-interfaces:interfaces/interface[name='eth/0/0']/type",
+            "iana-if-type:ethernetCsmacd",
+        ),
+        (
+            "/ietf-interfaces:interfaces/interface[name='eth/0/0']/oper-status",
+            "up",
+        ),
+        (
+            "/ietf-interfaces:interfaces/interface[name='eth/0/0']/statistics/in-octets",
+            "10",
+        ),
+    ] {
+        dtree
+            .new_path(xpath, Some(value), false)
+            .expect("Failed to edit data tree");
+    }
+    let dnode = dtree
+        .find_path("/ietf-interfaces:interfaces", false)
+        .expect("Failed to lookup data");
+
+    assert_eq!(
+        dnode
+            .traverse_config()
+            .map(|dnode| dnode.path())
+            .collect::<Vec<String>>(),
+        vec![
+            "/ietf-interfaces:interfaces",
+            "/ietf-interfaces:interfaces/interface[name='eth/0/0']",
+            "/ietf-interfaces:interfaces/interface[name='eth/0/0']/name",
+            "/ietf-interfaces:interfaces/interface[name='eth/0/0']/type",
+        ]
+    );
+    assert_eq!(dnode.traverse().count(), 7);
+}
+
+#[test]
+fn data_walk() {
+    struct Printer(String);
+
+    impl<'a> DataVisitor<'a> for Printer {
+        fn enter(&mut self, dnode: &DataNodeRef<'a>, _depth: usize) {
+            self.0.push_str(dnode.schema().name());
+            self.0.push('{');
+        }
+
+        fn leave(&mut self, _dnode: &DataNodeRef<'a>, _depth: usize) {
+            self.0.push('}');
+        }
+    }
+
+    let ctx = create_context();
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+    let dnode = dtree1
+        .find_path(
+            "/ietf-interfaces:interfaces/interface[name='eth/0/0']",
+            false,
+        )
+        .expect("Failed to lookup data");
+
+    let mut printer = Printer(String::new());
+    dnode.walk(&mut printer);
+    assert_eq!(printer.0, "interface{name{}description{}type{}enabled{}}");
+
+    let dnode = dtree1
+        .find_path("/ietf-interfaces:interfaces", false)
+        .expect("Failed to lookup data");
+    assert_eq!(
+        dnode.fold(0, |count, _dnode, _depth| count + 1),
+        dnode.traverse().count()
+    );
+    assert_eq!(
+        dnode.fold(0, |max, _dnode, depth| std::cmp::max(max, depth)),
+        2
+    );
+}
+
+#[test]
+fn data_walk_with_context() {
+    let ctx = create_context();
+    let dtree = parse_json_data(&ctx, JSON_TREE1);
+    let dnode = dtree
+        .find_path("/ietf-interfaces:interfaces", false)
+        .expect("Failed to lookup data");
+
+    let events = dnode.walk_with_context().collect::<Vec<WalkEvent<'_>>>();
+    assert_eq!(events.len(), 11);
+    assert!(events[0].parent.is_none());
+    assert!(events[0].is_last);
+
+    // First interface and its first leaf.
+    assert_eq!(events[1].parent.as_ref(), Some(&dnode));
+    assert!(!events[1].is_last);
+    assert_eq!(events[2].parent.as_ref(), Some(&events[1].node));
+    assert!(!events[2].is_last);
+
+    // Last interface and its last leaf.
+    assert_eq!(events[6].parent.as_ref(), Some(&dnode));
+    assert!(events[6].is_last);
+    assert_eq!(events[10].node.schema().name(), "enabled");
+    assert_eq!(events[10].parent.as_ref(), Some(&events[6].node));
+    assert!(events[10].is_last);
+}
+
 #[test]
 fn data_iterator_traverse_notification() {
     let ctx = create_context();
@@ -572,31 +1937,93 @@ fn data_iterator_traverse_rpc() {
 }
 
 #[test]
-fn data_iterator_ancestors() {
+fn data_iterator_lists() {
     let ctx = create_context();
     let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+    let dnode = dtree1
+        .find_path("/ietf-interfaces:interfaces", false)
+        .expect("Failed to lookup data");
 
+    let lists = dnode.lists().collect::<Vec<_>>();
+    assert_eq!(lists.len(), 1);
+    let (snode, entries) = &lists[0];
+    assert_eq!(snode.name(), "interface");
     assert_eq!(
-        dtree1
-            .find_path(
-                "/ietf-interfaces:interfaces/interface[name='eth/0/0']/type",
-            )
-            .expect("Failed to lookup data")
-            .ancestors()
+        entries
+            .iter()
             .map(|dnode| dnode.path())
             .collect::<Vec<String>>(),
         vec![
             "/ietf-interfaces:interfaces/interface[name='eth/0/0']",
-            "/ietf-interfaces:interfaces",
+            "/ietf-interfaces:interfaces/interface[name='eth/0/1']",
         ]
     );
-    assert_eq!(
-        dtree1
-            .find_path(
-                "/ietf-interfaces:interfaces/interface[name='eth/0/0']/type",
-            )
-            .expect("Failed to lookup data")
-            .inclusive_ancestors()
+    assert_eq!(dnode.list_entries(snode).count(), 2);
+
+    // Leafs aren't lists.
+    let dnode = &entries[0];
+    assert_eq!(dnode.lists().count(), 0);
+}
+
+#[test]
+fn data_iterator_same_schema_siblings() {
+    let ctx = create_context();
+    let dtree = parse_json_data(
+        &ctx,
+        r###"{
+            "yang2-test:misc": {
+                "user-ordered": ["a", "b"],
+                "system-ordered": ["x"],
+                "enabled": true
+            }
+        }"###,
+    );
+
+    let dnode = dtree
+        .find_path("/yang2-test:misc/user-ordered[.='a']", false)
+        .expect("Failed to lookup data");
+    assert_eq!(
+        dnode
+            .same_schema_siblings()
+            .map(|dnode| dnode.value_canonical().unwrap())
+            .collect::<Vec<String>>(),
+        vec!["b"]
+    );
+
+    let dnode = dtree
+        .find_path("/yang2-test:misc/system-ordered[.='x']", false)
+        .expect("Failed to lookup data");
+    assert_eq!(dnode.same_schema_siblings().count(), 0);
+}
+
+#[test]
+fn data_iterator_ancestors() {
+    let ctx = create_context();
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+
+    assert_eq!(
+        dtree1
+            .find_path(
+                "/ietf-interfaces:interfaces/interface[name='eth/0/0']/type",
+                false
+            )
+            .expect("Failed to lookup data")
+            .ancestors()
+            .map(|dnode| dnode.path())
+            .collect::<Vec<String>>(),
+        vec![
+            "/ietf-interfaces:interfaces/interface[name='eth/0/0']",
+            "/ietf-interfaces:interfaces",
+        ]
+    );
+    assert_eq!(
+        dtree1
+            .find_path(
+                "/ietf-interfaces:interfaces/interface[name='eth/0/0']/type",
+                false
+            )
+            .expect("Failed to lookup data")
+            .inclusive_ancestors()
             .map(|dnode| dnode.path())
             .collect::<Vec<String>>(),
         vec![
@@ -614,7 +2041,10 @@ fn data_iterator_siblings() {
 
     assert_eq!(
         dtree1
-            .find_path("/ietf-interfaces:interfaces/interface[name='eth/0/0']")
+            .find_path(
+                "/ietf-interfaces:interfaces/interface[name='eth/0/0']",
+                false
+            )
             .expect("Failed to lookup data")
             .siblings()
             .map(|dnode| dnode.path())
@@ -623,7 +2053,10 @@ fn data_iterator_siblings() {
     );
     assert_eq!(
         dtree1
-            .find_path("/ietf-interfaces:interfaces/interface[name='eth/0/0']")
+            .find_path(
+                "/ietf-interfaces:interfaces/interface[name='eth/0/0']",
+                false
+            )
             .expect("Failed to lookup data")
             .inclusive_siblings()
             .map(|dnode| dnode.path())
@@ -642,7 +2075,7 @@ fn data_iterator_children() {
 
     assert_eq!(
         dtree1
-            .find_path("/ietf-interfaces:interfaces")
+            .find_path("/ietf-interfaces:interfaces", false)
             .expect("Failed to lookup data")
             .children()
             .map(|dnode| dnode.path())
@@ -662,7 +2095,8 @@ fn data_is_default() {
     assert_eq!(
         dtree2
             .find_path(
-                "/ietf-interfaces:interfaces/interface[name='eth/0/0']/enabled"
+                "/ietf-interfaces:interfaces/interface[name='eth/0/0']/enabled",
+                false
             )
             .expect("Failed to lookup data")
             .is_default(),
@@ -671,10 +2105,1737 @@ fn data_is_default() {
     assert_eq!(
         dtree2
             .find_path(
-                "/ietf-interfaces:interfaces/interface[name='eth/0/2']/enabled"
+                "/ietf-interfaces:interfaces/interface[name='eth/0/2']/enabled",
+                false
             )
             .expect("Failed to lookup data")
             .is_default(),
         true,
     );
 }
+
+#[test]
+fn data_describe() {
+    let ctx = create_context();
+    let dtree = parse_json_data(&ctx, JSON_TREE1);
+
+    let dnode = dtree
+        .find_path(
+            "/ietf-interfaces:interfaces/interface[name='eth/0/0']/enabled",
+            false,
+        )
+        .expect("Failed to lookup data");
+    let description = dnode.describe();
+    assert_eq!(
+        description.node_type,
+        DataNodeType::Schema(SchemaNodeKind::Leaf)
+    );
+    assert_eq!(description.value_type, Some(DataValueType::Bool));
+    assert_eq!(description.value.as_deref(), Some("true"));
+    assert!(description.is_default);
+    assert_eq!(
+        description.to_string(),
+        "/ietf-interfaces:interfaces/interface[name='eth/0/0']/enabled \
+         (Leaf, Bool) = true [default]"
+    );
+
+    let dnode = dtree
+        .find_path("/ietf-interfaces:interfaces", false)
+        .expect("Failed to lookup data");
+    assert_eq!(
+        dnode.describe().to_string(),
+        "/ietf-interfaces:interfaces (Container)"
+    );
+}
+
+#[test]
+fn data_default_nodes() {
+    let ctx = create_context();
+    let dtree = DataTree::parse_string(
+        &ctx,
+        r###"{
+            "ietf-interfaces:interfaces": {
+                "interface": [
+                    {
+                        "name": "eth/0/0",
+                        "type": "iana-if-type:ethernetCsmacd",
+                        "enabled": true
+                    },
+                    {
+                        "name": "eth/0/1",
+                        "type": "iana-if-type:ethernetCsmacd"
+                    }
+                ]
+            }
+        }"###,
+        DataFormat::JSON,
+        DataParserFlags::empty(),
+        DataValidationFlags::NO_STATE,
+    )
+    .expect("Failed to parse data tree");
+
+    let defaults = dtree
+        .default_nodes()
+        .map(|dnode| dnode.path())
+        .collect::<BTreeSet<String>>();
+    assert!(defaults.contains(
+        "/ietf-interfaces:interfaces/interface[name='eth/0/1']/enabled"
+    ));
+    assert!(!defaults.contains(
+        "/ietf-interfaces:interfaces/interface[name='eth/0/0']/enabled"
+    ));
+    // Only the descendants of a data node are considered.
+    let path = "/ietf-interfaces:interfaces/interface[name='eth/0/0']";
+    let dnode = dtree.find_path(path, false).expect("Failed to lookup data");
+    assert!(dnode
+        .default_nodes()
+        .all(|dnode| dnode.path().starts_with(path)));
+    let dnode = dtree
+        .find_path(&format!("{}/enabled", path), false)
+        .expect("Failed to lookup data");
+    assert_eq!(dnode.default_nodes().count(), 0);
+}
+
+#[test]
+fn data_reset_to_default() {
+    let ctx = create_context();
+    let mut dtree = parse_json_data(&ctx, JSON_TREE2);
+    let path = "/ietf-interfaces:interfaces/interface[name='eth/0/0']";
+    let value = |dtree: &DataTree, path: &str| {
+        dtree
+            .find_path(path, false)
+            .expect("Failed to lookup data")
+            .value_canonical()
+    };
+
+    let enabled = format!("{}/enabled", path);
+    assert_eq!(value(&dtree, &enabled).as_deref(), Some("false"));
+    dtree
+        .reset_to_default(&enabled)
+        .expect("Failed to reset leaf");
+    assert_eq!(value(&dtree, &enabled).as_deref(), Some("true"));
+    assert_eq!(
+        dtree
+            .default_nodes()
+            .map(|dnode| dnode.path())
+            .collect::<Vec<String>>(),
+        vec![enabled]
+    );
+
+    // Leaf without a default value.
+    let description = format!("{}/description", path);
+    assert!(dtree.reset_to_default(&description).is_err());
+    assert_eq!(value(&dtree, &description).as_deref(), Some("ENG"));
+
+    // Not a leaf.
+    assert!(dtree.reset_to_default(path).is_err());
+
+    // Non-presence containers left with default nodes only are printed as
+    // if libyang had created the default value.
+    let isis = "/ietf-routing:routing/control-plane-protocols/control-plane-protocol[type='ietf-isis:isis'][name='main']/ietf-isis:isis";
+    let mut dtree1 = DataTree::new(&ctx);
+    dtree1
+        .new_path(&format!("{}/area-address", isis), Some("00"), false)
+        .expect("Failed to edit data tree");
+    let mut dtree2 = dtree1.duplicate().expect("Failed to duplicate data");
+    let status = format!("{}/overload/status", isis);
+    dtree1
+        .new_path(&status, Some("true"), false)
+        .expect("Failed to edit data tree");
+    for dtree in &mut [&mut dtree1, &mut dtree2] {
+        dtree
+            .add_implicit(DataImplicitFlags::NO_STATE)
+            .expect("Failed to add implicit nodes");
+    }
+    dtree1
+        .reset_to_default(&status)
+        .expect("Failed to reset leaf");
+    for options in &[DataPrinterFlags::WD_TRIM, DataPrinterFlags::WD_EXPLICIT] {
+        let options = DataPrinterFlags::WITH_SIBLINGS | *options;
+        assert_eq!(
+            dtree1
+                .print_string(DataFormat::JSON, options)
+                .expect("Failed to print data"),
+            dtree2
+                .print_string(DataFormat::JSON, options)
+                .expect("Failed to print data")
+        );
+    }
+}
+
+#[test]
+fn data_find_xpath_json_format() {
+    let ctx = create_context();
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+
+    let error = dtree1
+        .find_xpath("/interfaces/interface")
+        .expect_err("Missing module prefix not detected");
+    assert!(error.msg.unwrap().contains("\"interfaces\""));
+    assert!(dtree1
+        .find_path("/interfaces/interface[name='eth/0/0']", false)
+        .is_err());
+    assert!(dtree1.find_xpath("/*").is_ok());
+
+    // Relative expressions aren't affected.
+    let dnode = dtree1
+        .find_path("/ietf-interfaces:interfaces", false)
+        .expect("Failed to lookup data");
+    assert_eq!(
+        dnode
+            .find_xpath("interface/name")
+            .expect("Failed to lookup data")
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn data_any_value() {
+    let ctx = create_context();
+    let mut dtree = DataTree::new(&ctx);
+
+    dtree
+        .new_path("/yang2-test:misc/any-data", Some("test"), false)
+        .expect("Failed to edit data tree");
+    let dnode = dtree
+        .find_path("/yang2-test:misc/any-data", false)
+        .expect("Failed to lookup data");
+    match dnode.any_value() {
+        Some(DataAnyValue::String(value)) => assert_eq!(value, "test"),
+        value => panic!("Unexpected anydata value: {:?}", value),
+    }
+    assert_eq!(
+        dnode
+            .any_value_string()
+            .expect("Failed to print anydata value"),
+        Some("test".to_owned())
+    );
+
+    let dnode = dtree
+        .find_path("/yang2-test:misc", false)
+        .expect("Failed to lookup data");
+    assert!(dnode.any_value().is_none());
+    assert_eq!(dnode.any_value_string(), Ok(None));
+
+    // Data tree values.
+    let dtree = parse_json_data(
+        &ctx,
+        r###"{"yang2-test:misc": {"any-data": {"foo": "bar"}}}"###,
+    );
+    let dnode = dtree
+        .find_path("/yang2-test:misc/any-data", false)
+        .expect("Failed to lookup data");
+    match dnode.any_value() {
+        Some(DataAnyValue::DataTree(_)) => (),
+        value => panic!("Unexpected anydata value: {:?}", value),
+    }
+    assert!(dnode
+        .any_value_string()
+        .expect("Failed to print anydata value")
+        .unwrap()
+        .contains("\"bar\""));
+
+    // Serialized JSON and XML values.
+    let mut dtree = DataTree::new(&ctx);
+    let misc = dtree
+        .new_path("/yang2-test:misc", None, false)
+        .expect("Failed to edit data tree")
+        .expect("Failed to create node");
+    for (name, value, value_type) in &[
+        (
+            "any-data",
+            r###"{"foo":"baz"}"###,
+            ffi::LYD_ANYDATA_VALUETYPE::LYD_ANYDATA_JSON,
+        ),
+        (
+            "any-xml",
+            "<foo>baz</foo>",
+            ffi::LYD_ANYDATA_VALUETYPE::LYD_ANYDATA_XML,
+        ),
+    ] {
+        let name_cstr = CString::new(*name).unwrap();
+        let value_cstr = CString::new(*value).unwrap();
+        let ret = unsafe {
+            ffi::lyd_new_any(
+                misc.raw(),
+                std::ptr::null(),
+                name_cstr.as_ptr(),
+                value_cstr.as_ptr() as *const _,
+                0,
+                *value_type,
+                0,
+                std::ptr::null_mut(),
+            )
+        };
+        assert_eq!(ret, ffi::LY_ERR::LY_SUCCESS);
+    }
+    let dnode = dtree
+        .find_path("/yang2-test:misc/any-data", false)
+        .expect("Failed to lookup data");
+    match dnode.any_value() {
+        Some(DataAnyValue::Json(value)) => {
+            assert_eq!(value, r###"{"foo":"baz"}"###)
+        }
+        value => panic!("Unexpected anydata value: {:?}", value),
+    }
+    let dnode = dtree
+        .find_path("/yang2-test:misc/any-xml", false)
+        .expect("Failed to lookup data");
+    match dnode.any_value() {
+        Some(DataAnyValue::Xml(value)) => assert_eq!(value, "<foo>baz</foo>"),
+        value => panic!("Unexpected anyxml value: {:?}", value),
+    }
+    assert_eq!(
+        dnode
+            .any_value_string()
+            .expect("Failed to print anyxml value"),
+        Some("<foo>baz</foo>".to_owned())
+    );
+}
+
+#[test]
+fn data_decimal64() {
+    let ctx = create_context();
+    let mut dtree = DataTree::new(&ctx);
+
+    for (path, value) in &[
+        ("/yang2-test:misc/decimal", "-12.5"),
+        ("/yang2-test:misc/union", "0.01"),
+    ] {
+        dtree
+            .new_path(path, Some(value), false)
+            .expect("Failed to edit data tree");
+    }
+
+    let dnode = dtree
+        .find_path("/yang2-test:misc/decimal", false)
+        .expect("Failed to lookup data");
+    assert_eq!(dnode.decimal64(), Some((-12500, 3)));
+    let dnode = dtree
+        .find_path("/yang2-test:misc/union", false)
+        .expect("Failed to lookup data");
+    assert_eq!(dnode.decimal64(), Some((1, 2)));
+
+    dtree
+        .new_path("/yang2-test:misc/union", Some("none"), false)
+        .expect("Failed to edit data tree");
+    let dnode = dtree
+        .find_path("/yang2-test:misc/union", false)
+        .expect("Failed to lookup data");
+    assert_eq!(dnode.decimal64(), None);
+
+    // Opaque nodes have no decimal64 value.
+    let dnode = dtree
+        .new_opaq(None, "decimal", Some("1.5"), "urn:unknown")
+        .expect("Failed to create opaque node");
+    assert_eq!(dnode.decimal64(), None);
+}
+
+#[test]
+fn data_new_opaq() {
+    let ctx = create_context();
+    let mut dtree = DataTree::new(&ctx);
+
+    let path = dtree
+        .new_opaq(None, "config", None, "urn:example:proxy")
+        .expect("Failed to create opaque node")
+        .path();
+    dtree
+        .new_attr(&path, None, "type", Some("subtree"))
+        .expect("Failed to create attribute");
+    let dnode = dtree.relocate(&path).expect("Failed to lookup data");
+    assert_eq!(
+        dnode
+            .attrs()
+            .map(|attr| (attr.name().to_owned(), attr.value().to_owned()))
+            .collect::<Vec<_>>(),
+        vec![("type".to_owned(), "subtree".to_owned())]
+    );
+    dtree
+        .new_opaq(
+            Some(&path),
+            "hostname",
+            Some("router1"),
+            "urn:example:proxy",
+        )
+        .expect("Failed to create opaque node");
+
+    let xml = dtree
+        .print_string(DataFormat::XML, DataPrinterFlags::SHRINK)
+        .expect("Failed to print data")
+        .unwrap();
+    assert_eq!(
+        xml,
+        "<config xmlns=\"urn:example:proxy\" type=\"subtree\">\
+         <hostname>router1</hostname></config>"
+    );
+}
+
+#[test]
+fn data_node_type() {
+    let ctx = create_context();
+    let mut dtree = parse_json_data(&ctx, JSON_TREE1);
+
+    assert_eq!(
+        dtree
+            .traverse()
+            .take(3)
+            .map(|dnode| dnode.node_type())
+            .collect::<Vec<_>>(),
+        vec![
+            DataNodeType::Schema(SchemaNodeKind::Container),
+            DataNodeType::Schema(SchemaNodeKind::List),
+            DataNodeType::Schema(SchemaNodeKind::Leaf),
+        ]
+    );
+
+    let dnode = dtree
+        .new_opaq(None, "config", None, "urn:example:proxy")
+        .expect("Failed to create opaque node");
+    assert_eq!(dnode.node_type(), DataNodeType::Opaque);
+}
+
+#[test]
+fn data_print_restconf() {
+    let ctx = create_context();
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+
+    // Single data resource.
+    let dnode = dtree1
+        .find_path(
+            "/ietf-interfaces:interfaces/interface[name='eth/0/0']",
+            false,
+        )
+        .expect("Failed to lookup data");
+    assert_eq!(
+        dnode
+            .print_restconf(DataFormat::JSON, DataPrinterFlags::SHRINK, false)
+            .expect("Failed to print data"),
+        "{\"ietf-interfaces:interface\":[{\"name\":\"eth/0/0\",\
+         \"description\":\"ENG\",\"type\":\"iana-if-type:ethernetCsmacd\",\
+         \"enabled\":true}]}"
+    );
+
+    // Datastore resource.
+    let json = dtree1
+        .print_restconf(DataFormat::JSON, DataPrinterFlags::SHRINK, true)
+        .expect("Failed to print data");
+    assert!(json.starts_with(
+        "{\"ietf-restconf:data\":{\"ietf-interfaces:interfaces\":{"
+    ));
+    assert!(json.ends_with("}}}"));
+    let json = DataTree::new(&ctx)
+        .print_restconf(DataFormat::JSON, DataPrinterFlags::SHRINK, true)
+        .expect("Failed to print data");
+    assert_eq!(json, "{\"ietf-restconf:data\":{}}");
+
+    assert!(DataTree::new(&ctx)
+        .print_restconf(DataFormat::JSON, DataPrinterFlags::empty(), false)
+        .is_err());
+    assert!(dtree1
+        .print_restconf(DataFormat::LYB, DataPrinterFlags::empty(), true)
+        .is_err());
+}
+
+#[test]
+fn data_resolved_type() {
+    let ctx = create_context();
+    let mut dtree = DataTree::new(&ctx);
+
+    for (value, base_type) in &[
+        ("0.01", DataValueType::Dec64),
+        ("none", DataValueType::String),
+    ] {
+        dtree
+            .new_path("/yang2-test:misc/union", Some(value), false)
+            .expect("Failed to edit data tree");
+        let dnode = dtree
+            .find_path("/yang2-test:misc/union", false)
+            .expect("Failed to lookup data");
+        assert_eq!(
+            dnode.schema().leaf_type().unwrap().base_type(),
+            DataValueType::Union
+        );
+        assert_eq!(dnode.resolved_type().unwrap().base_type(), *base_type);
+    }
+
+    let dnode = dtree
+        .find_path("/yang2-test:misc", false)
+        .expect("Failed to lookup data");
+    assert!(dnode.resolved_type().is_none());
+
+    // Opaque nodes have no resolved type.
+    let dnode = dtree
+        .new_opaq(None, "union", Some("none"), "urn:unknown")
+        .expect("Failed to create opaque node");
+    assert!(dnode.resolved_type().is_none());
+}
+
+#[test]
+fn data_parser_chunks() {
+    let ctx = create_context();
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+
+    let mut parser = DataParser::new(
+        &ctx,
+        DataFormat::JSON,
+        DataParserFlags::NO_VALIDATION,
+        DataValidationFlags::empty(),
+    );
+    for chunk in JSON_TREE1.as_bytes().chunks(16) {
+        parser.feed(chunk);
+    }
+    let dtree2 = parser.finish().expect("Failed to parse data tree");
+    assert_data_eq!(&dtree1, &dtree2);
+
+    let mut parser = DataParser::new(
+        &ctx,
+        DataFormat::JSON,
+        DataParserFlags::NO_VALIDATION,
+        DataValidationFlags::empty(),
+    );
+    parser.feed(&JSON_TREE1.as_bytes()[..32]);
+    assert!(parser.finish().is_err());
+
+    // LYB data is only accepted by the unsafe parser, which rejects streams
+    // cut short before the end of the LYB header.
+    let data = dtree1
+        .print_bytes(DataFormat::LYB, DataPrinterFlags::WITH_SIBLINGS)
+        .expect("Failed to print data");
+    let parser = |chunks: &[&[u8]]| {
+        let mut parser = DataParser::new(
+            &ctx,
+            DataFormat::LYB,
+            DataParserFlags::NO_VALIDATION,
+            DataValidationFlags::empty(),
+        );
+        for chunk in chunks {
+            parser.feed(chunk);
+        }
+        parser
+    };
+    let dtree2 =
+        unsafe { parser(&data.chunks(16).collect::<Vec<_>>()).finish_lyb() }
+            .expect("Failed to parse data tree");
+    assert_data_eq!(&dtree1, &dtree2);
+    assert!(parser(&[&data]).finish().is_err());
+    assert!(unsafe { parser(&[]).finish_lyb() }.is_err());
+    assert!(unsafe { parser(&[&data[..6]]).finish_lyb() }.is_err());
+}
+
+#[test]
+fn data_collect_values() {
+    let ctx = create_context();
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+
+    assert_eq!(
+        dtree1
+            .collect_values("/ietf-interfaces:interfaces/interface/name")
+            .expect("Failed to lookup data"),
+        vec!["eth/0/0", "eth/0/1"]
+    );
+    assert_eq!(
+        dtree1
+            .collect_values("/ietf-interfaces:interfaces/interface")
+            .expect("Failed to lookup data"),
+        Vec::<String>::new()
+    );
+    assert!(dtree1.collect_values("interfaces/interface/name").is_err());
+}
+
+#[test]
+fn data_move_to() {
+    let ctx = create_context();
+    let mut dtree = DataTree::new(&ctx);
+    for (xpath, value) in &[
+        (
+            "/ietf-interfaces:interfaces/interface[name='eth/0/0']",
+            None,
+        ),
+        (
+            "/ietf-interfaces:interfaces/interface[name='eth/0/1']/description",
+            Some("MKT"),
+        ),
+    ] {
+        dtree
+            .new_path(xpath, *value, false)
+            .expect("Failed to edit data tree");
+    }
+
+    let path =
+        "/ietf-interfaces:interfaces/interface[name='eth/0/1']/description";
+
+    // Invalid parents.
+    assert!(dtree.move_to(path, "/ietf-interfaces:interfaces").is_err());
+    assert!(dtree
+        .move_to(
+            "/ietf-interfaces:interfaces/interface[name='eth/0/1']",
+            path
+        )
+        .is_err());
+    assert!(dtree
+        .move_to(
+            path,
+            "/ietf-interfaces:interfaces/interface[name='eth/0/2']"
+        )
+        .is_err());
+
+    let dnode = dtree
+        .move_to(
+            path,
+            "/ietf-interfaces:interfaces/interface[name='eth/0/0']",
+        )
+        .expect("Failed to move node");
+    assert_eq!(
+        dnode.path(),
+        "/ietf-interfaces:interfaces/interface[name='eth/0/0']/description"
+    );
+    assert!(dtree.find_path(path, false).is_err());
+}
+
+#[test]
+fn data_move_before_after() {
+    let ctx = create_context();
+    let mut dtree = parse_json_data(
+        &ctx,
+        r###"{
+            "yang2-test:misc": {
+                "user-ordered": ["a", "b", "c"],
+                "system-ordered": ["x", "y"]
+            }
+        }"###,
+    );
+    let values = |dtree: &DataTree| {
+        dtree
+            .collect_values("/yang2-test:misc/user-ordered")
+            .unwrap()
+    };
+    let user_ordered =
+        |value: &str| format!("/yang2-test:misc/user-ordered[.='{}']", value);
+    let system_ordered =
+        |value: &str| format!("/yang2-test:misc/system-ordered[.='{}']", value);
+
+    dtree
+        .move_before(&user_ordered("c"), &user_ordered("a"))
+        .expect("Failed to move node");
+    assert_eq!(values(&dtree), vec!["c", "a", "b"]);
+
+    dtree
+        .move_after(&user_ordered("c"), &user_ordered("b"))
+        .expect("Failed to move node");
+    assert_eq!(values(&dtree), vec!["a", "b", "c"]);
+
+    // Invalid anchors.
+    assert!(dtree
+        .move_after(&user_ordered("c"), &user_ordered("c"))
+        .is_err());
+    assert!(dtree
+        .move_before(&user_ordered("c"), &system_ordered("x"))
+        .is_err());
+    assert!(dtree
+        .move_before(&system_ordered("y"), &system_ordered("x"))
+        .is_err());
+    assert_eq!(values(&dtree), vec!["a", "b", "c"]);
+}
+
+#[test]
+fn data_print_indentation() {
+    let ctx = create_context();
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+
+    let json = dtree1
+        .print_string(DataFormat::JSON, DataPrinterFlags::WITH_SIBLINGS)
+        .expect("Failed to print data")
+        .unwrap();
+    let mut lines = json.lines();
+    assert_eq!(lines.next(), Some("{"));
+    assert_eq!(lines.next(), Some("  \"ietf-interfaces:interfaces\": {"));
+    assert_eq!(lines.next(), Some("    \"interface\": ["));
+}
+
+#[test]
+fn data_key_values() {
+    let ctx = create_context();
+    let mut dtree = DataTree::new(&ctx);
+    let xpath = "/ietf-routing:routing/control-plane-protocols/control-plane-protocol[name='main'][type='ietf-isis:isis']";
+    dtree
+        .new_path(xpath, None, false)
+        .expect("Failed to edit data tree");
+
+    let dnode = dtree
+        .find_path(xpath, false)
+        .expect("Failed to lookup data");
+    assert_eq!(
+        dnode.key_values(),
+        vec![
+            ("type".to_owned(), "ietf-isis:isis".to_owned()),
+            ("name".to_owned(), "main".to_owned()),
+        ]
+    );
+
+    let dnode = dtree
+        .find_path("/ietf-routing:routing", false)
+        .expect("Failed to lookup data");
+    assert!(dnode.key_values().is_empty());
+}
+
+#[test]
+fn data_relocate() {
+    let ctx = create_context();
+    let mut dtree1 = parse_json_data(&ctx, JSON_TREE1);
+
+    let path = dtree1
+        .find_path(
+            "/ietf-interfaces:interfaces/interface[name='eth/0/1']",
+            false,
+        )
+        .expect("Failed to lookup data")
+        .path();
+
+    // Modify the data tree while holding only the path.
+    dtree1
+        .new_path(
+            "/ietf-interfaces:interfaces/interface[name='eth/0/1']/enabled",
+            Some("false"),
+            false,
+        )
+        .expect("Failed to edit data tree");
+    let dnode = dtree1.relocate(&path).expect("Failed to relocate node");
+    assert_eq!(dnode.path(), path);
+
+    dtree1.remove(&path).expect("Failed to remove data");
+    assert!(dtree1.relocate(&path).is_none());
+}
+
+#[test]
+fn data_drop_order() {
+    let ctx = create_context();
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+    let dtree2 = parse_json_data(&ctx, JSON_TREE2);
+    let diff = dtree1
+        .diff(&dtree2, DataDiffFlags::empty())
+        .expect("Failed to compare data trees");
+    assert_eq!(Arc::strong_count(&ctx), 4);
+
+    // The data trees keep the context alive.
+    let weak = Arc::downgrade(&ctx);
+    drop(ctx);
+    assert!(weak.upgrade().is_some());
+    assert_eq!(
+        dtree1
+            .find_xpath("/ietf-interfaces:interfaces/interface")
+            .expect("Failed to lookup data")
+            .count(),
+        2
+    );
+    drop(dtree1);
+    drop(dtree2);
+    assert_eq!(diff.iter().count(), 3);
+
+    // The context is freed along with the last data tree.
+    drop(diff);
+    assert!(weak.upgrade().is_none());
+}
+
+#[test]
+fn data_set_value_typed() {
+    let ctx = create_context();
+    let mut dtree1 = parse_json_data(&ctx, JSON_TREE1);
+    let path = "/ietf-interfaces:interfaces/interface[name='eth/0/0']/enabled";
+    let value = |dtree: &DataTree, path: &str| {
+        dtree
+            .find_path(path, false)
+            .expect("Failed to lookup data")
+            .value()
+    };
+
+    dtree1
+        .set_value_typed(path, DataValue::Bool(false))
+        .expect("Failed to change value");
+    assert_eq!(value(&dtree1, path), Some(DataValue::Bool(false)));
+    dtree1
+        .set_value_typed(path, DataValue::Bool(false))
+        .expect("Failed to change value");
+    assert!(dtree1.set_value_typed(path, DataValue::Uint32(42)).is_err());
+    assert_eq!(value(&dtree1, path), Some(DataValue::Bool(false)));
+
+    assert!(dtree1
+        .set_value_typed(
+            "/ietf-interfaces:interfaces/interface[name='eth/0/0']",
+            DataValue::Empty,
+        )
+        .is_err());
+
+    let mut dtree2 = DataTree::new(&ctx);
+    let path = "/yang2-test:misc/decimal";
+    dtree2
+        .new_path(path, Some("0"), false)
+        .expect("Failed to edit data tree");
+    dtree2
+        .set_value_typed(path, DataValue::Other("-1.25".to_owned()))
+        .expect("Failed to change value");
+    assert_eq!(
+        dtree2
+            .find_path(path, false)
+            .expect("Failed to lookup data")
+            .decimal64(),
+        Some((-1250, 3))
+    );
+
+    // Opaque nodes have no typed value.
+    let path = dtree2
+        .new_opaq(None, "unknown", Some("value"), "urn:unknown")
+        .expect("Failed to create opaque node")
+        .path();
+    assert!(dtree2
+        .set_value_typed(&path, DataValue::Bool(true))
+        .is_err());
+}
+
+#[test]
+fn data_parse_bytes() {
+    let ctx = create_context();
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+
+    let mut data = JSON_TREE1.as_bytes().to_vec();
+    let dtree2 = DataTree::parse_bytes(
+        &ctx,
+        &data,
+        DataFormat::JSON,
+        DataParserFlags::NO_VALIDATION,
+        DataValidationFlags::empty(),
+    )
+    .expect("Failed to parse data tree");
+    assert_data_eq!(&dtree1, &dtree2);
+
+    // Null-terminated buffer.
+    data.push(0);
+    let dtree2 = DataTree::parse_bytes(
+        &ctx,
+        &data,
+        DataFormat::JSON,
+        DataParserFlags::NO_VALIDATION,
+        DataValidationFlags::empty(),
+    )
+    .expect("Failed to parse data tree");
+    drop(data);
+    assert_data_eq!(&dtree1, &dtree2);
+
+    assert!(DataTree::parse_bytes(
+        &ctx,
+        b"{}\0{}",
+        DataFormat::JSON,
+        DataParserFlags::NO_VALIDATION,
+        DataValidationFlags::empty(),
+    )
+    .is_err());
+}
+
+#[test]
+fn data_print_chunks() {
+    let ctx = create_context();
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+    let options = DataPrinterFlags::WITH_SIBLINGS;
+
+    let mut output = Vec::new();
+    dtree1
+        .print_chunks(DataFormat::JSON, options, |chunk| {
+            output.extend_from_slice(chunk);
+            Ok(())
+        })
+        .expect("Failed to print data");
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        dtree1
+            .print_string(DataFormat::JSON, options)
+            .expect("Failed to print data")
+            .unwrap()
+    );
+
+    // Errors from the callback abort printing.
+    let mut calls = 0;
+    let error = dtree1
+        .print_chunks(DataFormat::JSON, options, |_| {
+            calls += 1;
+            Err(std::io::Error::other("full"))
+        })
+        .unwrap_err();
+    assert_eq!(error.errcode, ffi::LY_ERR::LY_ESYS);
+    assert_eq!(error.msg.as_deref(), Some("full"));
+    assert_eq!(calls, 1);
+}
+
+#[test]
+fn data_print_lyb() {
+    let ctx = create_context();
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+
+    let data = dtree1
+        .print_bytes(DataFormat::LYB, DataPrinterFlags::WITH_SIBLINGS)
+        .expect("Failed to print data");
+    assert!(ctx.lyb_compatible(&data));
+    assert!(!ctx.lyb_compatible(b"lyb"));
+    // Data printed using another version of the LYB format.
+    let mut data2 = data.clone();
+    data2[3] = !data2[3];
+    assert!(!ctx.lyb_compatible(&data2));
+    assert!(!ctx.lyb_compatible(JSON_TREE1.as_bytes()));
+
+    let dtree2 = unsafe {
+        DataTree::parse_lyb(
+            &ctx,
+            &data,
+            DataParserFlags::NO_VALIDATION,
+            DataValidationFlags::empty(),
+        )
+    }
+    .expect("Failed to parse data tree");
+    assert_data_eq!(&dtree1, &dtree2);
+
+    // LYB data is only accepted by the unsafe parser, which rejects buffers
+    // without a complete LYB header.
+    assert!(DataTree::parse_bytes(
+        &ctx,
+        &data,
+        DataFormat::LYB,
+        DataParserFlags::NO_VALIDATION,
+        DataValidationFlags::empty(),
+    )
+    .is_err());
+    for data in &[&[][..], &data[..6], JSON_TREE1.as_bytes()] {
+        assert!(unsafe {
+            DataTree::parse_lyb(
+                &ctx,
+                data,
+                DataParserFlags::NO_VALIDATION,
+                DataValidationFlags::empty(),
+            )
+        }
+        .is_err());
+    }
+
+    // Data printed with a different set of modules.
+    let mut ctx2 = Context::new(ContextFlags::NO_YANGLIBRARY)
+        .expect("Failed to create context");
+    ctx2.set_searchdir(SEARCH_DIR)
+        .expect("Failed to set YANG search directory");
+    ctx2.load_module("ietf-interfaces", None, &[])
+        .expect("Failed to load module");
+    ctx2.load_module("iana-if-type", None, &[])
+        .expect("Failed to load module");
+    assert!(!ctx2.lyb_compatible(&data));
+
+    // Data printed before the module set of the context changed.
+    let mut ctx2 = Arc::new(ctx2);
+    let dtree3 = parse_json_data(
+        &ctx2,
+        r###"{"ietf-interfaces:interfaces": {"interface": [{
+            "name": "eth/0/0",
+            "type": "iana-if-type:ethernetCsmacd"
+        }]}}"###,
+    );
+    let data3 = dtree3
+        .print_bytes(DataFormat::LYB, DataPrinterFlags::WITH_SIBLINGS)
+        .expect("Failed to print data");
+    assert!(ctx2.lyb_compatible(&data3));
+    drop(dtree3);
+    Arc::get_mut(&mut ctx2)
+        .expect("Context is shared")
+        .load_module("ietf-ip", None, &[])
+        .expect("Failed to load module");
+    assert!(!ctx2.lyb_compatible(&data3));
+
+    // Non-binary formats.
+    let data = dtree1
+        .print_bytes(DataFormat::JSON, DataPrinterFlags::WITH_SIBLINGS)
+        .expect("Failed to print data");
+    assert_eq!(
+        Some(String::from_utf8(data).unwrap()),
+        dtree1
+            .print_string(DataFormat::JSON, DataPrinterFlags::WITH_SIBLINGS)
+            .expect("Failed to print data")
+    );
+}
+
+#[test]
+fn data_print_subtree_file() {
+    let ctx = create_context();
+    let dtree = parse_json_data(&ctx, JSON_TREE1);
+    let dnode = dtree
+        .find_path(
+            "/ietf-interfaces:interfaces/interface[name='eth/0/0']",
+            false,
+        )
+        .expect("Failed to lookup data");
+
+    let path = std::env::temp_dir()
+        .join(format!("yang2-print-subtree-{}.json", std::process::id()));
+    let file = std::fs::File::create(&path).expect("Failed to create file");
+    dnode
+        .print_subtree_file(
+            file,
+            DataFormat::JSON,
+            DataPrinterFlags::WITH_SIBLINGS,
+        )
+        .expect("Failed to print data");
+    let data = std::fs::read_to_string(&path).expect("Failed to read file");
+    std::fs::remove_file(&path).expect("Failed to remove file");
+
+    assert_eq!(
+        Some(data),
+        dnode
+            .print_string(DataFormat::JSON, DataPrinterFlags::empty())
+            .expect("Failed to print data")
+    );
+}
+
+#[test]
+fn data_parse_lenient() {
+    let ctx = create_context();
+    let data = r###"
+    {
+        "ietf-interfaces:interfaces":{
+            "interface": [
+                {
+                    "name": "eth/0/0",
+                    "type": "iana-if-type:ethernetCsmacd",
+                    "enabled": "maybe"
+                },
+                {
+                    "name": "eth/0/1",
+                    "type": "iana-if-type:ethernetCsmacd",
+                    "enabled": false
+                }
+            ]
+        }
+    }"###;
+
+    assert!(DataTree::parse_string(
+        &ctx,
+        data,
+        DataFormat::JSON,
+        DataParserFlags::NO_VALIDATION,
+        DataValidationFlags::empty(),
+    )
+    .is_err());
+
+    let (dtree, errors) = DataTree::parse_lenient(
+        &ctx,
+        data,
+        DataFormat::JSON,
+        DataParserFlags::empty(),
+    )
+    .expect("Failed to parse data tree");
+    assert_eq!(errors.len(), 1);
+    assert!(dtree
+        .find_path(
+            "/ietf-interfaces:interfaces/interface[name='eth/0/1']/enabled",
+            false
+        )
+        .is_ok());
+
+    // Malformed input.
+    assert!(DataTree::parse_lenient(
+        &ctx,
+        "{\"ietf-interfaces:interfaces\":",
+        DataFormat::JSON,
+        DataParserFlags::empty(),
+    )
+    .is_err());
+}
+
+#[test]
+fn data_parse_no_defaults() {
+    let ctx = create_context();
+    let data = r###"
+    {
+        "ietf-interfaces:interfaces":{
+            "interface": [
+                {
+                    "name": "eth/0/0",
+                    "type": "iana-if-type:ethernetCsmacd"
+                }
+            ]
+        }
+    }"###;
+    let xpath = "/ietf-interfaces:interfaces/interface[name='eth/0/0']/enabled";
+
+    let dtree = DataTree::parse_string(
+        &ctx,
+        data,
+        DataFormat::JSON,
+        DataParserFlags::empty(),
+        DataValidationFlags::NO_STATE | DataValidationFlags::PRESENT,
+    )
+    .expect("Failed to parse data tree");
+    assert!(dtree.find_path(xpath, false).is_ok());
+
+    let dtree = DataTree::parse_string(
+        &ctx,
+        data,
+        DataFormat::JSON,
+        DataParserFlags::empty(),
+        DataValidationFlags::NO_STATE
+            | DataValidationFlags::PRESENT
+            | DataValidationFlags::NO_DEFAULTS,
+    )
+    .expect("Failed to parse data tree");
+    assert!(dtree.find_path(xpath, false).is_err());
+}
+
+#[test]
+fn data_validate_report_defaults() {
+    let ctx = create_context();
+    let data = r###"
+    {
+        "ietf-interfaces:interfaces":{
+            "interface": [
+                {
+                    "name": "eth/0/0",
+                    "type": "iana-if-type:ethernetCsmacd"
+                }
+            ]
+        }
+    }"###;
+    let options = DataValidationFlags::NO_STATE | DataValidationFlags::PRESENT;
+
+    let mut dtree = DataTree::parse_string(
+        &ctx,
+        data,
+        DataFormat::JSON,
+        DataParserFlags::NO_VALIDATION,
+        DataValidationFlags::empty(),
+    )
+    .expect("Failed to parse data tree");
+    assert_eq!(
+        dtree
+            .validate_report_defaults(options)
+            .expect("Failed to validate data tree"),
+        vec!["/ietf-interfaces:interfaces/interface[name='eth/0/0']/enabled"]
+    );
+    assert!(dtree
+        .validate_report_defaults(options)
+        .expect("Failed to validate data tree")
+        .is_empty());
+}
+
+#[test]
+fn data_new_skeleton() {
+    let mut ctx = Context::new(ContextFlags::NO_YANGLIBRARY)
+        .expect("Failed to create context");
+    ctx.set_searchdir(SEARCH_DIR)
+        .expect("Failed to set YANG search directory");
+    ctx.load_module("yang2-test-skeleton", None, &[])
+        .expect("Failed to load module");
+    let ctx = Arc::new(ctx);
+
+    let module = ctx.get_module_latest("yang2-test-skeleton").unwrap();
+    let mut dtree =
+        DataTree::new_skeleton(&ctx, &module).expect("Failed to create data");
+    assert_eq!(
+        dtree
+            .traverse()
+            .map(|dnode| dnode.path())
+            .collect::<BTreeSet<String>>(),
+        [
+            "/yang2-test-skeleton:config",
+            "/yang2-test-skeleton:config/name",
+            "/yang2-test-skeleton:config/mtu",
+            "/yang2-test-skeleton:config/timers",
+        ]
+        .iter()
+        .map(|path| path.to_string())
+        .collect::<BTreeSet<String>>()
+    );
+
+    let dnode = dtree
+        .find_path("/yang2-test-skeleton:config/name", false)
+        .expect("Failed to lookup data");
+    assert_eq!(dnode.value_canonical().as_deref(), Some(""));
+
+    // The mtu leaf is an opaque placeholder, so validation fails.
+    assert!(dtree.validate(DataValidationFlags::NO_STATE).is_err());
+}
+
+#[test]
+fn data_new_leaflist() {
+    let ctx = create_context();
+    let mut dtree = DataTree::new(&ctx);
+    dtree
+        .new_path("/yang2-test:misc", None, false)
+        .expect("Failed to edit data tree");
+
+    dtree
+        .new_leaflist(
+            Some("/yang2-test:misc"),
+            None,
+            "user-ordered",
+            &["c", "a", "b"],
+        )
+        .expect("Failed to create leaf-list entries");
+    dtree
+        .new_leaflist(None, Some("yang2-test"), "numbers", &["3", "1", "2"])
+        .expect("Failed to create leaf-list entries");
+    assert!(dtree
+        .new_leaflist(None, Some("yang2-test"), "numbers", &["4", "blabla"])
+        .is_err());
+    assert!(dtree
+        .new_leaflist(None, Some("blabla"), "numbers", &["4"])
+        .is_err());
+
+    assert_eq!(
+        dtree
+            .find_xpath("/yang2-test:misc/user-ordered")
+            .expect("Failed to lookup data")
+            .values()
+            .collect::<Vec<_>>(),
+        vec![
+            Some("c".to_owned()),
+            Some("a".to_owned()),
+            Some("b".to_owned())
+        ]
+    );
+    assert_eq!(
+        dtree
+            .find_xpath("/yang2-test:numbers")
+            .expect("Failed to lookup data")
+            .values()
+            .collect::<BTreeSet<_>>(),
+        [
+            Some("1".to_owned()),
+            Some("2".to_owned()),
+            Some("3".to_owned())
+        ]
+        .iter()
+        .cloned()
+        .collect::<BTreeSet<_>>()
+    );
+}
+
+#[test]
+fn data_error_kind() {
+    let ctx = create_context();
+
+    let error = DataTree::parse_string(
+        &ctx,
+        "{\"ietf-interfaces:interfaces\":",
+        DataFormat::JSON,
+        DataParserFlags::empty(),
+        DataValidationFlags::NO_STATE,
+    )
+    .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::SyntaxError);
+    assert_eq!(error.vecode(), ffi::LY_VECODE::LYVE_SYNTAX_JSON);
+
+    let error = DataTree::parse_string(
+        &ctx,
+        r###"{"ietf-interfaces:interfaces":{"interface":[{"name":"eth/0/0"}]}}"###,
+        DataFormat::JSON,
+        DataParserFlags::empty(),
+        DataValidationFlags::NO_STATE | DataValidationFlags::PRESENT,
+    )
+    .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::ValidationFailed);
+
+    let dtree = parse_json_data(&ctx, JSON_TREE1);
+    let error = dtree.find_path("interfaces", false).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidValue);
+}
+
+#[test]
+fn data_error_display() {
+    let ctx = create_context();
+    let dtree = parse_json_data(&ctx, JSON_TREE1);
+
+    let error = dtree.find_path("interfaces", false).unwrap_err();
+    assert_eq!(error.to_string(), error.msg.clone().unwrap());
+
+    let mut error = error;
+    error.msg = Some("Invalid data".to_owned());
+    error.path = Some("/ietf-interfaces:interfaces".to_owned());
+    error.apptag = Some("too-many-elements".to_owned());
+    assert_eq!(
+        error.to_string(),
+        "Invalid data (path: /ietf-interfaces:interfaces) \
+         (app-tag: too-many-elements)"
+    );
+
+    // Errors can be converted into boxed standard errors.
+    let result: Result<(), Box<dyn std::error::Error>> = dtree
+        .find_path("interfaces", false)
+        .map(|_| ())
+        .map_err(Into::into);
+    assert!(result.is_err());
+}
+
+#[test]
+fn data_error_to_rpc_error() {
+    let ctx = create_context();
+    let mut dtree =
+        parse_json_data(&ctx, r###"{"yang2-test:misc": {"reference": "a"}}"###);
+    let error = dtree.validate(DataValidationFlags::NO_STATE).unwrap_err();
+
+    let rpc_error = error
+        .to_rpc_error(&ctx)
+        .expect("Failed to convert error")
+        .print_string(DataFormat::XML, DataPrinterFlags::SHRINK)
+        .expect("Failed to print data")
+        .unwrap();
+    assert!(rpc_error.starts_with(
+        "<rpc-error xmlns=\"urn:ietf:params:xml:ns:netconf:base:1.0\">\
+         <error-type>application</error-type>\
+         <error-tag>data-missing</error-tag>\
+         <error-severity>error</error-severity>\
+         <error-app-tag>instance-required</error-app-tag>"
+    ));
+
+    let mut error = error;
+    error.errcode = ffi::LY_ERR::LY_EEXIST;
+    error.msg = None;
+    error.path = None;
+    error.apptag = None;
+    let rpc_error = error
+        .to_rpc_error(&ctx)
+        .expect("Failed to convert error")
+        .print_string(DataFormat::XML, DataPrinterFlags::SHRINK)
+        .expect("Failed to print data")
+        .unwrap();
+    assert_eq!(
+        rpc_error,
+        "<rpc-error xmlns=\"urn:ietf:params:xml:ns:netconf:base:1.0\">\
+         <error-type>application</error-type>\
+         <error-tag>data-exists</error-tag>\
+         <error-severity>error</error-severity>\
+         </rpc-error>"
+    );
+}
+
+#[test]
+fn data_has_state_data() {
+    let ctx = create_context();
+    assert!(!DataTree::new(&ctx).has_state_data());
+
+    let mut dtree = parse_json_data(&ctx, JSON_TREE1);
+    assert!(!dtree.has_state_data());
+
+    dtree
+        .new_path(
+            "/ietf-interfaces:interfaces/interface[name='eth/0/0']/oper-status",
+            Some("up"),
+            false,
+        )
+        .expect("Failed to edit data tree");
+    assert!(dtree.has_state_data());
+}
+
+#[test]
+fn data_trim_to_xpath() {
+    let ctx = create_context();
+    let mut dtree = parse_json_data(&ctx, JSON_TREE1);
+
+    // Ancestors and list keys are kept.
+    dtree
+        .trim_to_xpath(
+            "/ietf-interfaces:interfaces/interface[name='eth/0/1']/description",
+        )
+        .expect("Failed to trim data tree");
+    assert_eq!(
+        dtree
+            .traverse()
+            .map(|dnode| dnode.path())
+            .collect::<Vec<String>>(),
+        vec![
+            "/ietf-interfaces:interfaces",
+            "/ietf-interfaces:interfaces/interface[name='eth/0/1']",
+            "/ietf-interfaces:interfaces/interface[name='eth/0/1']/name",
+            "/ietf-interfaces:interfaces/interface[name='eth/0/1']/description",
+        ]
+    );
+
+    // Whole subtrees of the selected nodes are kept.
+    let mut dtree = parse_json_data(&ctx, JSON_TREE1);
+    dtree
+        .trim_to_xpath("/ietf-interfaces:interfaces/interface[name='eth/0/0']")
+        .expect("Failed to trim data tree");
+    assert_eq!(dtree.node_count(), 6);
+
+    // Nothing selected.
+    dtree
+        .trim_to_xpath("/yang2-test:misc")
+        .expect("Failed to trim data tree");
+    assert!(dtree.reference().is_none());
+}
+
+#[test]
+fn data_node_count() {
+    let ctx = create_context();
+    let dtree = DataTree::new(&ctx);
+    assert_eq!(dtree.node_count(), 0);
+    assert_eq!(dtree.estimated_size(), 0);
+
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+    assert_eq!(dtree1.node_count(), 11);
+    assert!(dtree1.estimated_size() > 0);
+
+    let mut dtree2 = dtree1.duplicate().expect("Failed to duplicate data");
+    dtree2
+        .remove("/ietf-interfaces:interfaces/interface[name='eth/0/1']")
+        .expect("Failed to remove data");
+    assert_eq!(dtree2.node_count(), 6);
+    assert!(dtree2.estimated_size() < dtree1.estimated_size());
+
+    // Opaque nodes are accounted for as well.
+    let size = dtree2.estimated_size();
+    dtree2
+        .new_opaq(None, "unknown", Some("value"), "urn:unknown")
+        .expect("Failed to create opaque node");
+    assert_eq!(dtree2.node_count(), 7);
+    assert!(dtree2.estimated_size() > size);
+}
+
+#[test]
+fn data_parse_string_limited() {
+    let ctx = create_context();
+    let parse = |max_nodes| {
+        DataTree::parse_string_limited(
+            &ctx,
+            JSON_TREE1,
+            DataFormat::JSON,
+            DataParserFlags::NO_VALIDATION,
+            DataValidationFlags::empty(),
+            max_nodes,
+        )
+    };
+
+    let dtree = parse(11).expect("Failed to parse data");
+    assert_eq!(dtree.node_count(), 11);
+    assert!(parse(100).is_ok());
+
+    let error = parse(10).expect_err("Data tree should exceed the limit");
+    assert_eq!(error.errcode, ffi::LY_ERR::LY_EINVAL);
+    assert_eq!(
+        error.msg.as_deref(),
+        Some("Data tree exceeds the maximum of 10 nodes")
+    );
+    assert!(parse(0).is_err());
+}
+
+#[test]
+fn data_value_formatted() {
+    let ctx = create_context();
+    let mut dtree = parse_json_data(
+        &ctx,
+        r###"{"yang2-test:misc": {
+                "user-ordered": ["a"],
+                "identity": "yang2-test:derived-identity",
+                "instance": "/ietf-interfaces:interfaces/interface[name='eth/0/0']/description"
+            }}"###,
+    );
+    dtree
+        .merge(&parse_json_data(&ctx, JSON_TREE1))
+        .expect("Failed to merge data trees");
+    let formatted = |dtree: &DataTree, path: &str| {
+        let dnode = dtree.find_path(path, false).expect("Failed to find node");
+        [
+            ValueFormat::Canonical,
+            ValueFormat::Json,
+            ValueFormat::Xml,
+            ValueFormat::SchemaName,
+        ]
+        .iter()
+        .map(|format| dnode.value_formatted(*format))
+        .collect::<Vec<_>>()
+    };
+    let some = |value: &str| Some(value.to_owned());
+
+    assert_eq!(
+        formatted(&dtree, "/yang2-test:misc/identity"),
+        vec![
+            some("yang2-test:derived-identity"),
+            some("yang2-test:derived-identity"),
+            some("yt:derived-identity"),
+            some("yt:derived-identity"),
+        ]
+    );
+    assert_eq!(
+        formatted(&dtree, "/yang2-test:misc/instance"),
+        vec![
+            some("/ietf-interfaces:interfaces/interface[name='eth/0/0']/description"),
+            some("/ietf-interfaces:interfaces/interface[name='eth/0/0']/description"),
+            some("/if:interfaces/if:interface[if:name='eth/0/0']/if:description"),
+            None,
+        ]
+    );
+    assert_eq!(
+        formatted(
+            &dtree,
+            "/ietf-interfaces:interfaces/interface[name='eth/0/0']/type"
+        ),
+        vec![
+            some("iana-if-type:ethernetCsmacd"),
+            some("iana-if-type:ethernetCsmacd"),
+            some("ianaift:ethernetCsmacd"),
+            None,
+        ]
+    );
+    assert_eq!(
+        formatted(&dtree, "/yang2-test:misc/user-ordered"),
+        vec![some("a"), some("a"), some("a"), some("a")]
+    );
+
+    dtree
+        .new_path(
+            "/yang2-test:misc/instance",
+            Some("/yang2-test:misc/user-ordered[.='a']"),
+            false,
+        )
+        .expect("Failed to edit data tree");
+    assert_eq!(
+        formatted(&dtree, "/yang2-test:misc/instance")[3],
+        some("/yt:misc/yt:user-ordered[.='a']")
+    );
+
+    let dnode = dtree
+        .find_path("/yang2-test:misc", false)
+        .expect("Failed to find node");
+    assert!(dnode.value_formatted(ValueFormat::Xml).is_none());
+}
+
+#[test]
+fn data_value_equal() {
+    let ctx = create_context();
+    let dtree1 = parse_json_data(&ctx, JSON_TREE1);
+    let dtree2 = parse_json_data(&ctx, JSON_TREE2);
+
+    let enabled =
+        "/ietf-interfaces:interfaces/interface[name='eth/0/0']/enabled";
+    let dnode1 = dtree1
+        .find_path(enabled, false)
+        .expect("Failed to lookup data");
+    let dnode2 = dtree2
+        .find_path(enabled, false)
+        .expect("Failed to lookup data");
+    assert!(dnode1.value_equal(&dnode1));
+    assert!(!dnode1.value_equal(&dnode2));
+
+    let description =
+        "/ietf-interfaces:interfaces/interface[name='eth/0/0']/description";
+    let dnode1 = dtree1
+        .find_path(description, false)
+        .expect("Failed to lookup data");
+    let dnode2 = dtree2
+        .find_path(description, false)
+        .expect("Failed to lookup data");
+    assert!(dnode1.value_equal(&dnode2));
+
+    // Values of different types.
+    let mut dtree3 = DataTree::new(&ctx);
+    dtree3
+        .new_path("/yang2-test:misc/decimal", Some("1.5"), false)
+        .expect("Failed to edit data tree");
+    dtree3
+        .new_path("/yang2-test:misc/union", Some("1.50"), false)
+        .expect("Failed to edit data tree");
+    let decimal = dtree3
+        .find_path("/yang2-test:misc/decimal", false)
+        .expect("Failed to lookup data");
+    let union = dtree3
+        .find_path("/yang2-test:misc/union", false)
+        .expect("Failed to lookup data");
+    assert!(decimal.value_equal(&union));
+    assert!(!decimal.value_equal(&dnode1));
+
+    // Non-terminal nodes.
+    let dnode = dtree1
+        .find_path("/ietf-interfaces:interfaces", false)
+        .expect("Failed to lookup data");
+    assert!(!dnode.value_equal(&dnode));
+    assert!(!dnode1.value_equal(&dnode));
+}
+
+#[test]
+fn data_identityref_value() {
+    let ctx = create_context();
+    let dtree = parse_json_data(&ctx, JSON_TREE1);
+    let find =
+        |path: &str| dtree.find_path(path, false).expect("Failed to find node");
+
+    let dnode1 =
+        find("/ietf-interfaces:interfaces/interface[name='eth/0/0']/type");
+    let identity1 = dnode1.identityref_value().expect("Failed to get identity");
+    assert_eq!(identity1.name(), "ethernetCsmacd");
+    assert_eq!(identity1.module().name(), "iana-if-type");
+    assert_eq!(identity1.to_string(), "iana-if-type:ethernetCsmacd");
+
+    let dnode2 =
+        find("/ietf-interfaces:interfaces/interface[name='eth/0/1']/type");
+    assert!(dnode2.identityref_value() == Some(identity1));
+
+    let dnode3 =
+        find("/ietf-interfaces:interfaces/interface[name='eth/0/0']/name");
+    assert!(dnode3.identityref_value().is_none());
+}
+
+#[test]
+fn data_value_bytes() {
+    let ctx = create_context();
+    let mut dtree = DataTree::new(&ctx);
+    dtree
+        .new_path("/yang2-test:misc/binary", Some("aGVsbG8="), false)
+        .expect("Failed to edit data tree");
+    dtree
+        .new_path("/yang2-test:misc/binary-reference", Some("aGVsbG8="), false)
+        .expect("Failed to edit data tree");
+    dtree
+        .new_path("/yang2-test:misc/decimal", Some("1.5"), false)
+        .expect("Failed to edit data tree");
+
+    let dnode = dtree
+        .find_path("/yang2-test:misc/binary", false)
+        .expect("Failed to lookup data");
+    assert_eq!(dnode.value_bytes(), Some(b"hello".to_vec()));
+    let dnode = dtree
+        .find_path("/yang2-test:misc/binary-reference", false)
+        .expect("Failed to lookup data");
+    assert_eq!(dnode.value_bytes(), Some(b"hello".to_vec()));
+    let dnode = dtree
+        .find_path("/yang2-test:misc/decimal", false)
+        .expect("Failed to lookup data");
+    assert_eq!(dnode.value_bytes(), None);
+    let dnode = dtree
+        .find_path("/yang2-test:misc", false)
+        .expect("Failed to lookup data");
+    assert_eq!(dnode.value_bytes(), None);
+}
+
+#[test]
+fn data_when_satisfied() {
+    let ctx = create_context();
+    let mut dtree = parse_json_data(
+        &ctx,
+        r###"{"yang2-test:misc": {"enabled": true, "conditional": "on"}}"###,
+    );
+    let conditional = "/yang2-test:misc/conditional";
+
+    // Not validated yet.
+    let dnode = dtree
+        .find_path(conditional, false)
+        .expect("Failed to lookup data");
+    assert_eq!(dnode.when_satisfied(), None);
+    assert_eq!(
+        dnode
+            .schema()
+            .whens()
+            .map(|when| when.condition().to_owned())
+            .collect::<Vec<_>>(),
+        vec!["../enabled = 'true'"]
+    );
+
+    dtree
+        .validate(DataValidationFlags::NO_STATE)
+        .expect("Failed to validate data tree");
+    let dnode = dtree
+        .find_path(conditional, false)
+        .expect("Failed to lookup data");
+    assert_eq!(dnode.when_satisfied(), Some(true));
+    let dnode = dtree
+        .find_path("/yang2-test:misc/enabled", false)
+        .expect("Failed to lookup data");
+    assert_eq!(dnode.when_satisfied(), None);
+
+    // Nodes with a false "when" condition are deleted during validation.
+    dtree
+        .new_path("/yang2-test:misc/enabled", Some("false"), false)
+        .expect("Failed to edit data tree");
+    dtree
+        .validate(DataValidationFlags::NO_STATE)
+        .expect("Failed to validate data tree");
+    assert!(dtree.find_path(conditional, false).is_err());
+}
+
+#[test]
+fn data_user_data() {
+    let ctx = create_context();
+    let mut dtree = parse_json_data(&ctx, JSON_TREE1);
+    let counter = Arc::new(());
+
+    let paths = dtree
+        .traverse()
+        .map(|dnode| dnode.path())
+        .collect::<Vec<_>>();
+    for path in &paths {
+        dtree
+            .set_user_data(path, counter.clone())
+            .expect("Failed to set user data");
+    }
+    assert_eq!(Arc::strong_count(&counter), 12);
+
+    let path = "/ietf-interfaces:interfaces";
+    let dnode = dtree.find_path(path, false).expect("Failed to lookup data");
+    assert!(dnode.user_data::<Arc<()>>().is_some());
+    assert!(dnode.user_data::<String>().is_none());
+    dtree
+        .set_user_data(path, "interfaces".to_owned())
+        .expect("Failed to set user data");
+    let dnode = dtree.find_path(path, false).expect("Failed to lookup data");
+    assert_eq!(
+        dnode.user_data::<String>().map(String::as_str),
+        Some("interfaces")
+    );
+    assert_eq!(Arc::strong_count(&counter), 11);
+    dtree
+        .clear_user_data(path)
+        .expect("Failed to clear user data");
+    let dnode = dtree.find_path(path, false).expect("Failed to lookup data");
+    assert!(dnode.user_data::<String>().is_none());
+    assert!(dtree.set_user_data("/ietf-interfaces:foo", ()).is_err());
+
+    // User data is dropped along with the data nodes.
+    dtree
+        .remove("/ietf-interfaces:interfaces/interface[name='eth/0/0']")
+        .expect("Failed to remove data");
+    assert_eq!(Arc::strong_count(&counter), 6);
+    drop(dtree);
+    assert_eq!(Arc::strong_count(&counter), 1);
+
+    // Private pointers set by other means aren't taken as user data.
+    let mut dtree = parse_json_data(&ctx, JSON_TREE1);
+    let mut value = 0u32;
+    let ptr = &mut value as *mut u32 as *mut std::ffi::c_void;
+    let mut dnode =
+        dtree.find_path(path, false).expect("Failed to lookup data");
+    unsafe { dnode.set_private(ptr) };
+    assert!(dnode.user_data::<u32>().is_none());
+    assert!(dtree.set_user_data(path, 1u32).is_err());
+    dtree
+        .clear_user_data(path)
+        .expect("Failed to clear user data");
+    let dnode = dtree.find_path(path, false).expect("Failed to lookup data");
+    assert_eq!(dnode.get_private(), Some(ptr));
+}
+
+#[test]
+fn data_modules_with_data() {
+    let ctx = create_context();
+    let mut dtree = DataTree::new(&ctx);
+    assert!(dtree.modules_with_data().is_empty());
+
+    for xpath in &[
+        "/ietf-interfaces:interfaces/interface[name='eth/0/0']",
+        "/ietf-routing:routing/control-plane-protocols/control-plane-protocol[type='ietf-isis:isis'][name='main']",
+        "/ietf-interfaces:interfaces/interface[name='eth/0/1']",
+    ] {
+        dtree
+            .new_path(xpath, None, false)
+            .expect("Failed to edit data tree");
+    }
+
+    let mut modules = dtree
+        .modules_with_data()
+        .iter()
+        .map(|module| module.name().to_owned())
+        .collect::<Vec<_>>();
+    modules.sort();
+    assert_eq!(modules, vec!["ietf-interfaces", "ietf-routing"]);
+}
@@ -75,6 +75,25 @@ fn schema_find_path() {
         .is_ok());
 }
 
+#[test]
+fn schema_find_schema() {
+    let ctx = create_context();
+
+    let snode = ctx
+        .find_schema("/ietf-interfaces:interfaces/interface/name")
+        .expect("Failed to lookup schema node")
+        .expect("Schema node not found");
+    assert_eq!(snode.kind(), SchemaNodeKind::Leaf);
+    assert!(ctx
+        .find_schema("/ietf-interfaces:interfaces/blabla")
+        .expect("Failed to lookup schema node")
+        .is_none());
+    assert!(ctx.find_schema("/blabla:interfaces").is_err());
+    assert!(ctx
+        .find_schema("/ietf-interfaces:interfaces/interface/*")
+        .is_err());
+}
+
 #[test]
 fn schema_iterator_traverse() {
     let ctx = create_context();
@@ -331,3 +350,454 @@ fn schema_node_attributes() {
     assert_eq!(snode.is_status_deprecated(), true);
     assert_eq!(snode.is_status_obsolete(), false);
 }
+
+#[test]
+fn schema_node_extensions() {
+    let mut ctx = create_context();
+    ctx.load_module("ietf-key-chain", None, &[])
+        .expect("Failed to load module");
+
+    let snode = ctx
+        .find_path("/ietf-key-chain:key-chains/key-chain/key/key-string")
+        .expect("Failed to lookup schema node");
+    let exts = snode
+        .extensions()
+        .map(|ext| {
+            (
+                ext.module().name().to_owned(),
+                ext.name().to_owned(),
+                ext.argument().map(String::from),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        exts,
+        vec![(
+            "ietf-netconf-acm".to_owned(),
+            "default-deny-all".to_owned(),
+            None
+        )]
+    );
+
+    let snode = ctx
+        .find_path("/ietf-interfaces:interfaces/interface/enabled")
+        .expect("Failed to lookup schema node");
+    assert_eq!(snode.extensions().count(), 0);
+}
+
+#[test]
+fn schema_node_leaf_type() {
+    let mut ctx = Context::new(
+        ContextFlags::NO_YANGLIBRARY | ContextFlags::SET_PRIV_PARSED,
+    )
+    .expect("Failed to create context");
+    ctx.set_searchdir(SEARCH_DIR)
+        .expect("Failed to set YANG search directory");
+    ctx.load_module("ietf-ip", None, &[])
+        .expect("Failed to load module");
+
+    let ltype = ctx
+        .find_path("/ietf-interfaces:interfaces/interface/name")
+        .expect("Failed to lookup schema node")
+        .leaf_type()
+        .expect("Failed to get leaf type");
+    assert_eq!(ltype.base_type(), DataValueType::String);
+    assert_eq!(ltype.type_name(), Some("string"));
+    assert!(ltype.derived_from().is_empty());
+
+    let ltype = ctx
+        .find_path("/ietf-interfaces:interfaces-state/interface/speed")
+        .expect("Failed to lookup schema node")
+        .leaf_type()
+        .expect("Failed to get leaf type");
+    assert_eq!(ltype.base_type(), DataValueType::Uint64);
+    assert_eq!(ltype.type_name(), Some("yang:gauge64"));
+    assert_eq!(ltype.derived_from(), vec!["uint64"]);
+
+    let ltype = ctx
+        .find_path(
+            "/ietf-interfaces:interfaces/interface/ietf-ip:ipv4/address/ip",
+        )
+        .expect("Failed to lookup schema node")
+        .leaf_type()
+        .expect("Failed to get leaf type");
+    assert_eq!(ltype.base_type(), DataValueType::String);
+    assert_eq!(ltype.type_name(), Some("inet:ipv4-address-no-zone"));
+    assert_eq!(ltype.derived_from(), vec!["inet:ipv4-address", "string"]);
+
+    // Type names aren't available without the parsed schema.
+    let ctx = create_context();
+    let ltype = ctx
+        .find_path("/ietf-interfaces:interfaces/interface/name")
+        .expect("Failed to lookup schema node")
+        .leaf_type()
+        .expect("Failed to get leaf type");
+    assert_eq!(ltype.base_type(), DataValueType::String);
+    assert_eq!(ltype.type_name(), None);
+    assert!(ltype.derived_from().is_empty());
+}
+
+#[test]
+fn schema_node_priv_parsed() {
+    let mut ctx = create_context();
+    let snode = ctx
+        .find_path("/ietf-interfaces:interfaces/interface/type")
+        .expect("Failed to lookup schema node");
+    assert_eq!(snode.get_private(), None);
+    assert_eq!(snode.leaf_type().unwrap().type_name(), None);
+
+    // Retain parsed schema information in an existing context.
+    ctx.set_options(ContextFlags::SET_PRIV_PARSED)
+        .expect("Failed to set context options");
+    let snode = ctx
+        .find_path("/ietf-interfaces:interfaces/interface/type")
+        .expect("Failed to lookup schema node");
+    assert_eq!(snode.get_private(), None);
+    assert_eq!(snode.leaf_type().unwrap().type_name(), Some("identityref"));
+
+    ctx.unset_options(ContextFlags::SET_PRIV_PARSED)
+        .expect("Failed to unset context options");
+    let snode = ctx
+        .find_path("/ietf-interfaces:interfaces/interface/type")
+        .expect("Failed to lookup schema node");
+    assert_eq!(snode.leaf_type().unwrap().type_name(), None);
+}
+
+#[test]
+fn schema_node_kinds() {
+    let mut ctx = create_context();
+    ctx.load_module("yang2-test", None, &[])
+        .expect("Failed to load module");
+    ctx.load_module("ietf-isis", None, &[])
+        .expect("Failed to load module");
+
+    for (path, kind) in &[
+        ("/yang2-test:misc/any-data", SchemaNodeKind::AnyData),
+        ("/yang2-test:misc/any-xml", SchemaNodeKind::AnyXml),
+        ("/ietf-isis:clear-adjacency", SchemaNodeKind::Rpc),
+    ] {
+        let snode = ctx.find_path(path).expect("Failed to lookup schema node");
+        assert_eq!(snode.kind(), *kind);
+    }
+
+    let module = ctx.get_module_latest("ietf-isis").unwrap();
+    assert!(module
+        .rpcs()
+        .all(|snode| snode.kind() == SchemaNodeKind::Rpc));
+    assert!(module
+        .notifications()
+        .all(|snode| snode.kind() == SchemaNodeKind::Notification));
+    assert!(module.notifications().count() > 0);
+}
+
+#[test]
+fn schema_node_when_dependencies() {
+    let mut ctx = create_context();
+    ctx.load_module("yang2-test", None, &[])
+        .expect("Failed to load module");
+
+    let snode = ctx
+        .find_path("/yang2-test:misc/conditional")
+        .expect("Failed to lookup schema node");
+    let dependencies = snode
+        .when_dependencies()
+        .expect("Failed to find when dependencies")
+        .iter()
+        .map(|snode| snode.path(SchemaPathFormat::DATA))
+        .collect::<Vec<_>>();
+    assert!(dependencies.contains(&"/yang2-test:misc/enabled".to_owned()));
+    assert!(!dependencies.contains(&"/yang2-test:misc/conditional".to_owned()));
+
+    let snode = ctx
+        .find_path("/yang2-test:misc/enabled")
+        .expect("Failed to lookup schema node");
+    assert!(snode
+        .when_dependencies()
+        .expect("Failed to find when dependencies")
+        .is_empty());
+}
+
+#[test]
+fn schema_module_deviations() {
+    let mut ctx = create_context();
+    ctx.load_module("yang2-test", None, &[])
+        .expect("Failed to load module");
+
+    let module = ctx.get_module_latest("yang2-test").unwrap();
+    assert!(!module.is_deviated());
+    assert!(ctx.find_path("/yang2-test:misc/any-xml").is_ok());
+
+    ctx.load_module("yang2-test-deviations", None, &[])
+        .expect("Failed to load module");
+
+    let module = ctx.get_module_latest("yang2-test").unwrap();
+    assert!(module.is_deviated());
+    assert_eq!(
+        module
+            .deviated_by()
+            .iter()
+            .map(|module| module.name())
+            .collect::<Vec<_>>(),
+        vec!["yang2-test-deviations"]
+    );
+    assert!(ctx.find_path("/yang2-test:misc/any-xml").is_err());
+
+    // Modules deviating other modules aren't loaded.
+    assert!(ctx
+        .get_module_implemented("yang2-test-other-deviations")
+        .is_none());
+    let module = ctx.get_module_latest("yang2-test-deviations").unwrap();
+    assert!(!module.is_deviated());
+}
+
+#[test]
+fn schema_load_module_with_deviations() {
+    let mut ctx = create_context();
+    let module = ctx
+        .load_module_with_deviations("yang2-test", None, &[])
+        .expect("Failed to load module");
+    assert!(module.is_implemented());
+    let mut deviations = module
+        .deviated_by()
+        .iter()
+        .map(|module| module.name())
+        .collect::<Vec<_>>();
+    deviations.sort();
+    assert_eq!(
+        deviations,
+        vec!["yang2-test-deviations", "yang2-test-more-deviations"]
+    );
+    assert!(ctx.find_path("/yang2-test:misc/any-xml").is_err());
+    // Deviating modules are loaded from their files, whatever their names.
+    assert!(ctx.find_path("/yang2-test:misc/any-data").is_err());
+
+    // Modules deviating other modules aren't loaded.
+    assert!(ctx
+        .get_module_implemented("yang2-test-other-deviations")
+        .is_none());
+
+    // Modules without deviations.
+    let module = ctx
+        .load_module_with_deviations("yang2-test-skeleton", None, &[])
+        .expect("Failed to load module");
+    assert!(!module.is_deviated());
+
+    // Errors leave the context untouched.
+    assert!(ctx
+        .load_module_with_deviations("yang2-test-unknown", None, &[])
+        .is_err());
+    assert!(ctx.get_module_latest("yang2-test-unknown").is_none());
+}
+
+#[test]
+fn schema_node_user_ordered() {
+    let mut ctx = create_context();
+    ctx.load_module("yang2-test", None, &[])
+        .expect("Failed to load module");
+
+    for (path, user_ordered) in &[
+        ("/yang2-test:misc/user-ordered", true),
+        ("/yang2-test:misc/system-ordered", false),
+        ("/yang2-test:misc/decimal", false),
+        ("/ietf-interfaces:interfaces/interface", false),
+    ] {
+        let snode = ctx.find_path(path).expect("Failed to lookup schema node");
+        assert_eq!(snode.is_user_ordered(), *user_ordered);
+    }
+}
+
+#[test]
+fn schema_context_with_modules() {
+    let ctx = Context::new_with_modules(
+        ContextFlags::NO_YANGLIBRARY,
+        &[SEARCH_DIR],
+        &[
+            ("ietf-interfaces", &["pre-provisioning"]),
+            ("ietf-netconf-acm", &[]),
+        ],
+    )
+    .expect("Failed to create context");
+    let module = ctx.get_module_latest("ietf-interfaces").unwrap();
+    assert!(module.is_implemented());
+    assert_eq!(module.feature_value("pre-provisioning"), Ok(true));
+    let module = ctx.get_module_latest("ietf-netconf-acm").unwrap();
+    assert!(module.is_implemented());
+
+    // The search directory doesn't contain the ietf-netconf module.
+    assert!(Context::new_netconf(&[SEARCH_DIR]).is_err());
+}
+
+#[test]
+fn schema_module_imports() {
+    let mut ctx = create_context();
+    ctx.load_module("yang2-test-deviations", None, &[])
+        .expect("Failed to load module");
+
+    let module = ctx.get_module_latest("yang2-test").unwrap();
+    assert_eq!(
+        module
+            .imports()
+            .map(|module| module.name().to_owned())
+            .collect::<Vec<_>>(),
+        vec!["ietf-yang-types"]
+    );
+    assert_eq!(
+        module.includes().collect::<Vec<_>>(),
+        vec!["yang2-test-sub"]
+    );
+
+    let module = ctx.get_module_latest("yang2-test-deviations").unwrap();
+    assert_eq!(
+        module
+            .imports()
+            .map(|module| module.name().to_owned())
+            .collect::<Vec<_>>(),
+        vec!["yang2-test"]
+    );
+    assert_eq!(module.includes().count(), 0);
+}
+
+#[test]
+fn schema_context_snapshot() {
+    let mut ctx =
+        Context::new(ContextFlags::empty()).expect("Failed to create context");
+    ctx.set_searchdir(SEARCH_DIR)
+        .expect("Failed to set YANG search directory");
+    ctx.load_module("ietf-interfaces", None, &["pre-provisioning"])
+        .expect("Failed to load module");
+    let snapshot = ctx.snapshot().expect("Failed to take snapshot");
+
+    let ctx =
+        Context::from_snapshot(ContextFlags::empty(), &[SEARCH_DIR], &snapshot)
+            .expect("Failed to restore context");
+    let module = ctx.get_module_latest("ietf-interfaces").unwrap();
+    assert!(module.is_implemented());
+    assert_eq!(module.feature_value("pre-provisioning"), Ok(true));
+    assert_eq!(module.feature_value("if-mib"), Ok(false));
+
+    // Snapshots require the ietf-yang-library module.
+    let ctx = create_context();
+    assert!(ctx.snapshot().is_err());
+}
+
+#[test]
+fn schema_validate_value() {
+    let ctx = create_context();
+
+    let snode = ctx
+        .find_path("/ietf-interfaces:interfaces/interface/enabled")
+        .expect("Failed to lookup schema node");
+    assert!(snode.validate_value("true").is_ok());
+    let error = snode.validate_value("blabla").unwrap_err();
+    assert!(error.msg.is_some());
+
+    let snode = ctx
+        .find_path("/ietf-interfaces:interfaces/interface/type")
+        .expect("Failed to lookup schema node");
+    assert!(snode.validate_value("iana-if-type:ethernetCsmacd").is_ok());
+    assert!(snode.validate_value("iana-if-type:blabla").is_err());
+
+    let snode = ctx
+        .find_path("/ietf-interfaces:interfaces")
+        .expect("Failed to lookup schema node");
+    assert!(snode.validate_value("true").is_err());
+}
+
+#[test]
+fn schema_canonicalize_value() {
+    let mut ctx = create_context();
+    ctx.load_module("yang2-test", None, &[])
+        .expect("Failed to load module");
+
+    let snode = ctx
+        .find_path("/yang2-test:numbers")
+        .expect("Failed to lookup schema node");
+    assert_eq!(snode.canonicalize_value("0x1F"), Ok("31".to_owned()));
+    assert_eq!(snode.canonicalize_value("010"), Ok("8".to_owned()));
+    assert!(snode.canonicalize_value("256").is_err());
+
+    let snode = ctx
+        .find_path("/yang2-test:misc/decimal")
+        .expect("Failed to lookup schema node");
+    assert_eq!(snode.canonicalize_value("1.50"), Ok("1.5".to_owned()));
+}
+
+#[test]
+fn schema_context_operations() {
+    let mut ctx = create_context();
+    ctx.load_module("yang2-test", None, &[])
+        .expect("Failed to load module");
+
+    assert_eq!(
+        ctx.rpcs()
+            .map(|snode| snode.path(SchemaPathFormat::DATA))
+            .collect::<Vec<String>>(),
+        vec!["/yang2-test:echo"]
+    );
+    assert_eq!(
+        ctx.actions()
+            .map(|snode| snode.path(SchemaPathFormat::DATA))
+            .collect::<Vec<String>>(),
+        vec!["/yang2-test:misc/reset"]
+    );
+    assert_eq!(
+        ctx.notifications()
+            .map(|snode| snode.path(SchemaPathFormat::DATA))
+            .collect::<Vec<String>>(),
+        vec!["/yang2-test:misc/changed"]
+    );
+}
+
+#[test]
+fn schema_context_searchdirs() {
+    let mut ctx = create_context();
+    let search_dir = std::fs::canonicalize(SEARCH_DIR).unwrap();
+    assert_eq!(ctx.searchdirs(), vec![search_dir.clone()]);
+
+    ctx.set_searchdir("./src")
+        .expect("Failed to set YANG search directory");
+    assert_eq!(ctx.searchdirs().len(), 2);
+    ctx.unset_searchdir(&search_dir)
+        .expect("Failed to unset YANG search directory");
+    assert_eq!(
+        ctx.searchdirs(),
+        vec![std::fs::canonicalize("./src").unwrap()]
+    );
+    ctx.unset_searchdirs()
+        .expect("Failed to unset YANG search directories");
+    assert!(ctx.searchdirs().is_empty());
+}
+
+#[test]
+fn schema_context_namespace_prefixes() {
+    let ctx = create_context();
+    let prefixes = ctx.namespace_prefixes();
+    assert!(prefixes.contains(&(
+        "if".to_owned(),
+        "urn:ietf:params:xml:ns:yang:ietf-interfaces".to_owned()
+    )));
+    assert!(prefixes.contains(&(
+        "ianaift".to_owned(),
+        "urn:ietf:params:xml:ns:yang:iana-if-type".to_owned()
+    )));
+    assert_eq!(
+        prefixes.len(),
+        ctx.modules(false)
+            .map(|module| module.namespace().to_owned())
+            .collect::<std::collections::BTreeSet<_>>()
+            .len()
+    );
+}
+
+#[test]
+fn schema_name_lifetime() {
+    let ctx = create_context();
+
+    // Names are tied to the context, not to the node or module references.
+    let names = ctx
+        .traverse()
+        .filter(|snode| snode.kind() == SchemaNodeKind::List)
+        .map(|snode| (snode.module().name(), snode.name()))
+        .collect::<Vec<(&str, &str)>>();
+    assert!(names.contains(&("ietf-interfaces", "interface")));
+}